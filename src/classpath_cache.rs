@@ -0,0 +1,97 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, to_string, Value};
+use sha1::{Digest, Sha1};
+
+use crate::{layout::Layout, platform::TargetPlatform, version_json_path};
+
+// In the version dir, next to its JSON
+pub const CACHE_FILE_NAME: &str = "wmml-classpath.json";
+
+#[derive(Serialize, Deserialize)]
+struct CachedClasspath {
+    // The version and every version it inherits from, child first
+    chain: Vec<String>,
+    // SHA-1 of their JSONs, the layout and the platform
+    key: String,
+    entries: Vec<PathBuf>,
+}
+
+// The classpath recorded by an earlier launch while none of the version JSONs changed,
+// otherwise the one resolve builds, which is recorded for the next launch
+pub fn cached_classpath(
+    mc_path: &Path,
+    layout: &Layout,
+    version_id: &str,
+    platform: &TargetPlatform,
+    resolve: impl FnOnce() -> Vec<PathBuf>,
+) -> Vec<PathBuf> {
+    let cache_path = layout.version_dir(mc_path, version_id).join(CACHE_FILE_NAME);
+
+    let cached = fs::read_to_string(&cache_path)
+        .ok()
+        .and_then(|content| from_str::<CachedClasspath>(&content).ok());
+    if let Some(cached) = cached {
+        if cache_key(mc_path, layout, &cached.chain, platform).is_some_and(|key| key == cached.key) {
+            log::debug!("using the cached classpath of {}", version_id);
+            return cached.entries;
+        }
+    }
+
+    let entries = resolve();
+
+    // Stripped installs have no JSON to key on and are never cached
+    let Some((chain, key)) = inheritance_chain(mc_path, layout, version_id)
+        .and_then(|chain| cache_key(mc_path, layout, &chain, platform).map(|key| (chain, key)))
+    else {
+        return entries;
+    };
+
+    // Only a cache; the next launch resolves again if it cannot be written
+    let cache = CachedClasspath { chain, key, entries };
+    if let Ok(content) = to_string(&cache) {
+        let _ = fs::write(&cache_path, content);
+    }
+
+    cache.entries
+}
+
+fn inheritance_chain(mc_path: &Path, layout: &Layout, version_id: &str) -> Option<Vec<String>> {
+    let mut chain = vec![version_id.to_string()];
+
+    loop {
+        let content = fs::read_to_string(version_json_path(mc_path, layout, chain.last()?)).ok()?;
+        let json: Value = from_str(&content).ok()?;
+
+        match json["inheritsFrom"].as_str() {
+            Some(parent) if chain.iter().any(|id| id == parent) => return None,
+            Some(parent) => chain.push(parent.to_string()),
+            None => return Some(chain),
+        }
+    }
+}
+
+fn cache_key(mc_path: &Path, layout: &Layout, chain: &[String], platform: &TargetPlatform) -> Option<String> {
+    let mut hasher = Sha1::new();
+
+    for part in [mc_path, &layout.versions, &layout.libraries] {
+        hasher.update(part.to_string_lossy().as_bytes());
+        hasher.update([0]);
+    }
+    for part in [&platform.os, &platform.arch, platform.os_version.as_deref().unwrap_or("")] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+
+    for id in chain {
+        let content = fs::read(version_json_path(mc_path, layout, id)).ok()?;
+        hasher.update(id.as_bytes());
+        hasher.update([0]);
+        hasher.update(&content);
+    }
+
+    Some(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
@@ -0,0 +1,135 @@
+use std::{
+    io::{BufRead, BufReader, Read},
+    process::{Child, Stdio},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    collect_classpath, error::WmmlError, events, install, java, layout::Layout, manifest, normalize_path,
+    platform::TargetPlatform, prepare_launch, resolve_version, LaunchOptions,
+};
+
+// Checked when no version is named: it runs on the Java 17 that CI images ship and logs the
+// ready patterns of modern versions; its asset objects are never needed
+pub const DOCTOR_VERSION: &str = "1.20.1";
+// Used unless --timeout says otherwise
+const TIMEOUT: Duration = Duration::from_secs(180);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// Installs the version without its asset objects, launches it and waits for it to finish
+// loading; true when it did
+pub fn run_doctor(
+    mc_path: &str,
+    layout: &Layout,
+    version_name: &str,
+    player_name: &str,
+    options: &LaunchOptions,
+) -> Result<bool, WmmlError> {
+    match java::detect_java(&options.java_path(None)) {
+        Ok(java) => println!("[ok]   Java {} ({})", java.version, java.arch),
+        Err(e) => {
+            println!("[fail] {}", e);
+            return Ok(false);
+        }
+    }
+
+    let root = normalize_path(mc_path)?;
+    let version_id = manifest::resolve_version_alias(&root, version_name)?;
+    if let Err(e) = install::install_version(&root, layout, &version_id, TargetPlatform::host(), false, false, install::default_jobs()) {
+        println!("[fail] cannot install {}: {}", version_id, e);
+        return Ok(false);
+    }
+    println!("[ok]   {} installed", version_id);
+
+    let classpath = collect_classpath(&root, layout, &resolve_version(&root, layout, &version_id)?, TargetPlatform::host());
+    let missing = classpath.iter().filter(|p| !p.exists()).count();
+    if missing > 0 {
+        println!("[fail] {} of {} classpath entries are missing, see print-classpath", missing, classpath.len());
+        return Ok(false);
+    }
+    println!("[ok]   {} classpath entries present", classpath.len());
+
+    let prepared = prepare_launch(mc_path, layout, &version_id, player_name, options)?;
+    let mut child = prepared
+        .command()
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()?;
+
+    let (sender, receiver) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        thread::spawn(move || send_lines(stdout, sender));
+    }
+
+    let timeout = options.timeout.unwrap_or(TIMEOUT);
+    let deadline = Instant::now() + timeout;
+    let passed = loop {
+        match receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(line) if events::is_ready_line(&line) => {
+                println!("[ok]   game finished loading: {}", line.trim());
+                break true;
+            }
+            Ok(_) | Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => {}
+        }
+
+        // A closed output alone does not mean the game is gone
+        if let Some(status) = child.try_wait()? {
+            println!("[fail] game exited early ({})", status);
+            return Ok(false);
+        }
+        if Instant::now() >= deadline {
+            println!("[fail] game did not finish loading within {}s", timeout.as_secs());
+            break false;
+        }
+    };
+
+    stop_game(&mut child);
+
+    Ok(passed)
+}
+
+// The output is GB18030, so lines are decoded lossily instead of ending the read at the first
+// one that is no UTF-8
+fn send_lines(stdout: impl Read, sender: mpsc::Sender<String>) {
+    let mut reader = BufReader::new(stdout);
+    let mut line = Vec::new();
+
+    while reader.read_until(b'\n', &mut line).is_ok_and(|read| read > 0) {
+        if sender.send(String::from_utf8_lossy(&line).into_owned()).is_err() {
+            break;
+        }
+        line.clear();
+    }
+}
+
+// Anything java started goes with it
+fn stop_game(child: &mut Child) {
+    #[cfg(windows)]
+    let _ = std::process::Command::new("taskkill")
+        .args(["/F", "/T", "/PID"])
+        .arg(child.id().to_string())
+        .stdout(Stdio::null())
+        .status();
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_after_invalid_utf8_still_arrive() {
+        let output: &[u8] = b"\xc4\xe3\xba\xc3 GB18030\n[Render thread/INFO]: Sound engine started\n";
+        let (sender, receiver) = mpsc::channel();
+
+        send_lines(output, sender);
+        let lines: Vec<String> = receiver.into_iter().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(events::is_ready_line(&lines[1]));
+    }
+}
@@ -0,0 +1,381 @@
+use std::{
+    collections::HashMap,
+    env,
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Condvar, Mutex, OnceLock,
+    },
+};
+use ruzstd::decoding::StreamingDecoder;
+use sha1::{Digest, Sha1};
+
+use crate::mirror::Mirror;
+
+const MAX_CONNECTIONS_ENV: &str = "WMML_MAX_CONNECTIONS_PER_HOST";
+const DEFAULT_MAX_CONNECTIONS_PER_HOST: usize = 8;
+const CA_CERTS_ENV: &str = "WMML_CA_CERTS";
+
+// Hashes whatever reaches the file, i.e. the decompressed bytes
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha1,
+    written: u64,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// Downloads in flight per server, blocking and async alike; also the idle connections kept per server
+pub fn max_connections_per_host() -> usize {
+    static MAX: OnceLock<usize> = OnceLock::new();
+
+    *MAX.get_or_init(|| match env::var(MAX_CONNECTIONS_ENV) {
+        Ok(value) => value.trim().parse().ok().filter(|max| *max > 0).unwrap_or_else(|| {
+            log::warn!("invalid {} value {}, using {}", MAX_CONNECTIONS_ENV, value, DEFAULT_MAX_CONNECTIONS_PER_HOST);
+            DEFAULT_MAX_CONNECTIONS_PER_HOST
+        }),
+        Err(_) => DEFAULT_MAX_CONNECTIONS_PER_HOST,
+    })
+}
+
+// PEM file named by WMML_CA_CERTS, for proxies that re-sign TLS with their own CA. Its certificates
+// are trusted on top of the built-in roots, so hosts the proxy lets through keep working
+pub fn ca_certs_pem() -> Option<&'static [u8]> {
+    static PEM: OnceLock<Option<Vec<u8>>> = OnceLock::new();
+
+    PEM.get_or_init(|| {
+        let path = PathBuf::from(env::var_os(CA_CERTS_ENV)?);
+        fs::read(&path)
+            .inspect_err(|e| log::warn!("cannot read {} {}: {}, using the built-in roots", CA_CERTS_ENV, path.display(), e))
+            .ok()
+    })
+    .as_deref()
+}
+
+// Counts the requests in flight per host; waits while a host is at the limit
+struct HostSlots {
+    limit: usize,
+    taken: Mutex<HashMap<String, usize>>,
+    freed: Condvar,
+}
+
+// Frees its slot when the request is done with, body included
+struct HostSlot<'a> {
+    slots: &'a HostSlots,
+    host: String,
+}
+
+impl HostSlots {
+    fn new(limit: usize) -> Self {
+        HostSlots { limit, taken: Mutex::new(HashMap::new()), freed: Condvar::new() }
+    }
+
+    fn acquire(&self, host: &str) -> HostSlot<'_> {
+        let mut taken = self.taken.lock().unwrap_or_else(|e| e.into_inner());
+        while taken.get(host).is_some_and(|count| *count >= self.limit) {
+            taken = self.freed.wait(taken).unwrap_or_else(|e| e.into_inner());
+        }
+        *taken.entry(host.to_string()).or_insert(0) += 1;
+
+        HostSlot { slots: self, host: host.to_string() }
+    }
+}
+
+impl Drop for HostSlot<'_> {
+    fn drop(&mut self) {
+        let mut taken = self.slots.taken.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(count) = taken.get_mut(&self.host) {
+            *count -= 1;
+            if *count == 0 {
+                taken.remove(&self.host);
+            }
+        }
+        self.slots.freed.notify_all();
+    }
+}
+
+// The agent's pool only bounds idle connections, so the in-flight limit is kept here
+fn host_slot(url: &str) -> HostSlot<'static> {
+    static SLOTS: OnceLock<HostSlots> = OnceLock::new();
+
+    let host = url
+        .parse::<ureq::http::Uri>()
+        .ok()
+        .and_then(|uri| uri.host().map(String::from))
+        .unwrap_or_default();
+
+    SLOTS.get_or_init(|| HostSlots::new(max_connections_per_host())).acquire(&host)
+}
+
+// Shared by every request so the thousands of asset downloads reuse pooled connections
+pub fn agent() -> &'static ureq::Agent {
+    static AGENT: OnceLock<ureq::Agent> = OnceLock::new();
+
+    AGENT.get_or_init(|| {
+        let mut config = ureq::Agent::config_builder().max_idle_connections_per_host(max_connections_per_host());
+
+        if let Some(pem) = ca_certs_pem() {
+            let certs: Vec<_> = ureq::tls::parse_pem(pem)
+                .filter_map(|item| match item {
+                    Ok(ureq::tls::PemItem::Certificate(cert)) => Some(cert),
+                    _ => None,
+                })
+                .collect();
+
+            if certs.is_empty() {
+                log::warn!("no certificates in {}, using the built-in roots", CA_CERTS_ENV);
+            } else {
+                // The same Mozilla roots ureq trusts by default, plus the proxy's
+                let roots = webpki_root_certs::TLS_SERVER_ROOT_CERTS
+                    .iter()
+                    .map(|cert| ureq::tls::Certificate::from_der(cert.as_ref()))
+                    .chain(certs);
+                let tls = ureq::tls::TlsConfig::builder()
+                    .root_certs(ureq::tls::RootCerts::from(roots))
+                    .build();
+                config = config.tls_config(tls);
+            }
+        }
+
+        config.build().into()
+    })
+}
+
+pub fn download_file(url: &str, dest: &Path, sha1: Option<&str>) -> io::Result<()> {
+    download_file_with_progress(url, dest, sha1, |_| {})
+}
+
+// Reports whole percentages; without a usable length only 100 is reported at the end
+pub fn download_file_with_progress(
+    url: &str,
+    dest: &Path,
+    sha1: Option<&str>,
+    mut on_progress: impl FnMut(u8),
+) -> io::Result<()> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mirrors = Mirror::list_from_env();
+    let candidates = Mirror::candidates(&mirrors, url);
+    let mut last_error = None;
+
+    for (i, (mirror, url)) in candidates.iter().enumerate() {
+        match download_from(url, dest, sha1, &mut on_progress) {
+            Ok(()) => {
+                // Debug unless a fallback was needed; thousands of assets would drown the console
+                if i == 0 {
+                    log::debug!("{} served {}", mirror.name(), url);
+                } else {
+                    log::info!("{} served {}", mirror.name(), url);
+                }
+                return Ok(());
+            }
+            Err(e) => {
+                if i + 1 < candidates.len() {
+                    log::warn!("{}, trying the next mirror", e);
+                }
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| io::Error::other(format!("下载失败 {}: 没有可用的下载源", url))))
+}
+
+// A single attempt against one already rewritten URL
+fn download_from(
+    url: &str,
+    dest: &Path,
+    sha1: Option<&str>,
+    on_progress: &mut impl FnMut(u8),
+) -> io::Result<()> {
+    let _slot = host_slot(url);
+    let mut response = agent().get(url)
+        .header("Accept-Encoding", "gzip, zstd")
+        .call()
+        .map_err(|e| io::Error::other(format!("下载失败 {}: {}", url, e)))?;
+
+    // ureq decodes gzip itself; zstd bodies arrive still compressed
+    let is_zstd = response
+        .headers()
+        .get("Content-Encoding")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.trim().eq_ignore_ascii_case("zstd"));
+
+    // Content-Length counts the compressed bytes, so it only helps for identity transfers
+    let total = response
+        .headers()
+        .get("Content-Length")
+        .filter(|_| !response.headers().contains_key("Content-Encoding"))
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|total| *total > 0);
+
+    let body = response.body_mut().as_reader();
+    let mut reader: Box<dyn Read + '_> = if is_zstd {
+        Box::new(StreamingDecoder::new(body).map_err(|e| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("无法解压 {}: {}", url, e)))?)
+    } else {
+        Box::new(body)
+    };
+
+    let part_path = part_path(dest);
+
+    let mut writer = HashingWriter {
+        inner: File::create(&part_path)?,
+        hasher: Sha1::new(),
+        written: 0,
+    };
+
+    let mut buf = vec![0; 64 * 1024];
+    let mut last_pct = None;
+    loop {
+        let read = reader.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buf[..read])?;
+
+        if let Some(total) = total {
+            let pct = (writer.written * 100 / total).min(100) as u8;
+            if last_pct != Some(pct) {
+                last_pct = Some(pct);
+                on_progress(pct);
+            }
+        }
+    }
+    drop(reader);
+
+    if last_pct != Some(100) {
+        on_progress(100);
+    }
+
+    let HashingWriter { inner: file, hasher, .. } = writer;
+    drop(file);
+
+    if let Err(e) = check_sha1(url, hasher, sha1) {
+        let _ = fs::remove_file(&part_path);
+        return Err(e);
+    }
+
+    finish_part(&part_path, dest, sha1)
+}
+
+pub fn sha1_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha1::new();
+    let mut buf = vec![0; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// Write next to the destination first so an interrupted transfer never leaves a truncated file;
+// every transfer gets its own part, so installs sharing libraries and assets never write into
+// each other's
+pub fn part_path(dest: &Path) -> PathBuf {
+    static NEXT_PART: AtomicU64 = AtomicU64::new(0);
+
+    let mut part_path = OsString::from(dest.as_os_str());
+    part_path.push(format!(".{}-{}.part", process::id(), NEXT_PART.fetch_add(1, Ordering::Relaxed)));
+    PathBuf::from(part_path)
+}
+
+// Renaming over the destination is atomic, so readers see the old file or the new one. Windows
+// refuses while another install has the same file open; the copy already there is as good when
+// its hash matches
+pub fn finish_part(part_path: &Path, dest: &Path, sha1: Option<&str>) -> io::Result<()> {
+    let Err(e) = fs::rename(part_path, dest) else {
+        return Ok(());
+    };
+    let _ = fs::remove_file(part_path);
+
+    match sha1 {
+        Some(sha1) if sha1_file(dest).is_ok_and(|actual| actual.eq_ignore_ascii_case(sha1)) => Ok(()),
+        _ => Err(e),
+    }
+}
+
+// For files several launcher processes may write at once
+pub fn write_file_atomic(path: &Path, content: impl AsRef<[u8]>) -> io::Result<()> {
+    let part_path = part_path(path);
+    fs::write(&part_path, content)?;
+
+    fs::rename(&part_path, path).inspect_err(|_| {
+        let _ = fs::remove_file(&part_path);
+    })
+}
+
+pub fn check_sha1(url: &str, hasher: Sha1, expected: Option<&str>) -> io::Result<()> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+
+    let actual: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("文件校验失败 {}: 期望 {}, 实际 {}", url, expected, actual)));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::{atomic::AtomicBool, Arc},
+        thread,
+        time::Duration,
+    };
+
+    #[test]
+    fn requests_beyond_the_host_limit_wait_for_a_free_slot() {
+        let slots = Arc::new(HostSlots::new(2));
+        let first = slots.acquire("libraries.minecraft.net");
+        let _second = slots.acquire("libraries.minecraft.net");
+
+        let done = Arc::new(AtomicBool::new(false));
+        let waiter = {
+            let (slots, done) = (slots.clone(), done.clone());
+            thread::spawn(move || {
+                let _third = slots.acquire("libraries.minecraft.net");
+                done.store(true, Ordering::SeqCst);
+            })
+        };
+
+        // Other hosts are not held up by a full one
+        drop(slots.acquire("resources.download.minecraft.net"));
+        thread::sleep(Duration::from_millis(200));
+        assert!(!done.load(Ordering::SeqCst));
+
+        drop(first);
+        waiter.join().unwrap();
+        assert!(done.load(Ordering::SeqCst));
+    }
+}
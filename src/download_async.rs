@@ -0,0 +1,218 @@
+// Non-blocking counterparts for embedders running on tokio; the CLI itself stays blocking and
+// only reaches these through the install-async and install-optifine-async commands
+
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+};
+use sha1::{Digest, Sha1};
+use tokio::{fs, io::AsyncWriteExt, sync::Semaphore, task};
+
+use crate::{download, error::WmmlError, install, layout::Layout, mirror::Mirror, optifine, platform::TargetPlatform};
+
+// One pooled client for all downloads; HTTP/2 is negotiated where the server offers it
+fn client() -> io::Result<&'static reqwest::Client> {
+    static CLIENT: OnceLock<Result<reqwest::Client, String>> = OnceLock::new();
+
+    let client = CLIENT.get_or_init(|| {
+        let mut builder = reqwest::Client::builder().pool_max_idle_per_host(download::max_connections_per_host());
+
+        // Same WMML_CA_CERTS roots as the blocking agent, on top of the platform's
+        match download::ca_certs_pem().map(reqwest::Certificate::from_pem_bundle) {
+            Some(Ok(certs)) if !certs.is_empty() => builder = builder.tls_certs_merge(certs),
+            Some(Ok(_)) => log::warn!("no certificates in WMML_CA_CERTS, using the built-in roots"),
+            Some(Err(e)) => log::warn!("invalid WMML_CA_CERTS: {}, using the built-in roots", e),
+            None => {}
+        }
+
+        // A default client would quietly drop the proxy's roots
+        builder.build().map_err(|e| e.to_string())
+    });
+
+    client
+        .as_ref()
+        .map_err(|e| io::Error::other(format!("无法创建 HTTP 客户端: {}", e)))
+}
+
+// Concurrent downloads beyond the per-host limit wait for a free slot
+fn host_limit(url: &str) -> Arc<Semaphore> {
+    static LIMITS: OnceLock<Mutex<HashMap<String, Arc<Semaphore>>>> = OnceLock::new();
+
+    let host = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(String::from))
+        .unwrap_or_default();
+    let mut limits = LIMITS.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+
+    limits
+        .entry(host)
+        .or_insert_with(|| Arc::new(Semaphore::new(download::max_connections_per_host())))
+        .clone()
+}
+
+pub async fn download_file(url: &str, dest: &Path, sha1: Option<&str>) -> io::Result<()> {
+    download_file_with_progress(url, dest, sha1, |_| {}).await
+}
+
+// Same contract as download::download_file_with_progress; reqwest handles gzip and zstd itself
+pub async fn download_file_with_progress(
+    url: &str,
+    dest: &Path,
+    sha1: Option<&str>,
+    mut on_progress: impl FnMut(u8),
+) -> io::Result<()> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    let mirrors = Mirror::list_from_env();
+    let candidates = Mirror::candidates(&mirrors, url);
+    let mut last_error = None;
+
+    for (i, (mirror, url)) in candidates.iter().enumerate() {
+        match download_from(url, dest, sha1, &mut on_progress).await {
+            Ok(()) => {
+                if i == 0 {
+                    log::debug!("{} served {}", mirror.name(), url);
+                } else {
+                    log::info!("{} served {}", mirror.name(), url);
+                }
+                return Ok(());
+            }
+            Err(e) => {
+                if i + 1 < candidates.len() {
+                    log::warn!("{}, trying the next mirror", e);
+                }
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| io::Error::other(format!("下载失败 {}: 没有可用的下载源", url))))
+}
+
+async fn download_from(
+    url: &str,
+    dest: &Path,
+    sha1: Option<&str>,
+    on_progress: &mut impl FnMut(u8),
+) -> io::Result<()> {
+    let download_error = |e: reqwest::Error| io::Error::other(format!("下载失败 {}: {}", url, e));
+
+    let _permit = host_limit(url).acquire_owned().await.map_err(io::Error::other)?;
+    let mut response = client()?
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(download_error)?;
+    let total = response.content_length().filter(|total| *total > 0);
+
+    let part_path = download::part_path(dest);
+    let mut file = fs::File::create(&part_path).await?;
+    let mut hasher = Sha1::new();
+    let mut written = 0u64;
+    let mut last_pct = None;
+
+    while let Some(chunk) = response.chunk().await.map_err(download_error)? {
+        file.write_all(&chunk).await?;
+        hasher.update(&chunk);
+        written += chunk.len() as u64;
+
+        if let Some(total) = total {
+            let pct = (written * 100 / total).min(100) as u8;
+            if last_pct != Some(pct) {
+                last_pct = Some(pct);
+                on_progress(pct);
+            }
+        }
+    }
+    file.flush().await?;
+    drop(file);
+
+    if last_pct != Some(100) {
+        on_progress(100);
+    }
+
+    if let Err(e) = download::check_sha1(url, hasher, sha1) {
+        let _ = fs::remove_file(&part_path).await;
+        return Err(e);
+    }
+
+    // The fallback may hash the file already in place, which blocks
+    let (dest, sha1) = (dest.to_path_buf(), sha1.map(String::from));
+    task::spawn_blocking(move || download::finish_part(&part_path, &dest, sha1.as_deref()))
+        .await
+        .map_err(io::Error::other)?
+}
+
+// Same contract as install::install_version. The manifest, the version JSON and the asset index
+// are read on the blocking pool; the artifacts, nearly all of the bytes, go through the async
+// client with at most jobs downloads at once
+pub async fn install_version(
+    mc_path: PathBuf,
+    layout: Layout,
+    version_id: String,
+    platform: TargetPlatform,
+    server_jar: bool,
+    asset_objects: bool,
+    jobs: usize,
+) -> Result<(), WmmlError> {
+    let plan = task::spawn_blocking(move || {
+        install::plan_install(&mc_path, &layout, &version_id, &platform, server_jar, asset_objects)
+    })
+    .await
+    .map_err(io::Error::other)??;
+
+    let plan = Arc::new(plan);
+    let total = plan.artifacts.len();
+    let slots = Arc::new(Semaphore::new(jobs.max(1)));
+    let mut downloads = task::JoinSet::new();
+
+    for i in 0..total {
+        let (plan, slots) = (plan.clone(), slots.clone());
+
+        downloads.spawn(async move {
+            let _slot = slots.acquire_owned().await;
+
+            // Hashing what is already in place blocks as well
+            let check = plan.clone();
+            let intact = task::spawn_blocking(move || install::is_intact(&check.artifacts[i], check.check_hash(&check.artifacts[i]))).await;
+            if intact.unwrap_or(false) {
+                return (i, None);
+            }
+
+            let artifact = &plan.artifacts[i];
+            println!("[{}/{}] Downloading {}", i + 1, total, artifact.path.display());
+            match download_file(&artifact.url, &artifact.path, Some(&artifact.sha1)).await {
+                Ok(()) => (i, Some(true)),
+                Err(e) => {
+                    log::warn!("{}", e);
+                    (i, Some(false))
+                }
+            }
+        });
+    }
+
+    let mut results = vec![None; total];
+    while let Some(joined) = downloads.join_next().await {
+        let (i, result) = joined.map_err(io::Error::other)?;
+        results[i] = result;
+    }
+
+    plan.finish(&results)
+}
+
+// The installer runs java and unpacks zips, so it moves to the blocking pool
+pub async fn install_optifine(
+    mc_path: PathBuf,
+    layout: Layout,
+    installer: PathBuf,
+    java_path: String,
+) -> io::Result<String> {
+    task::spawn_blocking(move || optifine::install_optifine(&mc_path, &layout, &installer, &java_path))
+        .await
+        .map_err(io::Error::other)?
+}
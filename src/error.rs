@@ -0,0 +1,75 @@
+use std::{
+    error::Error,
+    fmt,
+    io,
+    path::PathBuf,
+};
+
+#[derive(Debug)]
+pub enum WmmlError {
+    Io(io::Error),
+    ReadOnlyDirectory(PathBuf),
+    GameNotOwned,
+    AlreadyRunning(u32),
+    // Bytes the download needs and bytes free on the disk holding the path
+    InsufficientSpace { path: PathBuf, required: u64, available: u64 },
+    // A skin file that is no PNG, or one whose size the game cannot use
+    InvalidSkin { path: PathBuf, size: Option<(u32, u32)> },
+    // Status and message of the services API refusing an upload
+    SkinRejected { status: u16, message: String },
+}
+
+impl fmt::Display for WmmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WmmlError::Io(e) => write!(f, "{}", e),
+            WmmlError::ReadOnlyDirectory(path) => write!(
+                f,
+                "目录不可写: {}，请为该实例使用一个可写的独立游戏目录",
+                path.display()),
+            WmmlError::GameNotOwned => write!(
+                f,
+                "该账户未拥有 Minecraft，可使用 --demo-if-unowned 启动试玩版"),
+            WmmlError::AlreadyRunning(pid) => write!(
+                f,
+                "该游戏目录已有实例在运行 (PID {})，可使用 --force 强制启动",
+                pid),
+            WmmlError::InsufficientSpace { path, required, available } => write!(
+                f,
+                "磁盘空间不足: {} 需要 {} MB，仅剩 {} MB",
+                path.display(),
+                required.div_ceil(1024 * 1024),
+                available / 1024 / 1024),
+            WmmlError::InvalidSkin { path, size: Some((width, height)) } => write!(
+                f,
+                "皮肤尺寸无效: {} 为 {}x{}，只能是 64x64 或 64x32",
+                path.display(),
+                width,
+                height),
+            WmmlError::InvalidSkin { path, size: None } => write!(
+                f,
+                "皮肤不是有效的 PNG 文件: {}",
+                path.display()),
+            WmmlError::SkinRejected { status, message } => write!(
+                f,
+                "皮肤上传被拒绝 (HTTP {}): {}",
+                status,
+                message),
+        }
+    }
+}
+
+impl Error for WmmlError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WmmlError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WmmlError {
+    fn from(e: io::Error) -> Self {
+        WmmlError::Io(e)
+    }
+}
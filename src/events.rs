@@ -0,0 +1,46 @@
+use crate::session::SessionOutcome;
+
+// Emitted in order while a launch runs; a channel sender can be captured in the handler
+#[derive(Debug, Clone)]
+pub enum LaunchEvent {
+    ResolvingVersion { version: String },
+    Downloading { name: String, pct: u8 },
+    BuildingArguments,
+    Spawning,
+    Spawned { pid: u32 },
+    // The game printed a line that shows it finished loading, with that line
+    Ready { line: String },
+    // Only sent when a handler is set, since the launcher then waits for the game
+    Exited { code: Option<i32>, outcome: SessionOutcome },
+}
+
+pub type EventHandler = Box<dyn Fn(&LaunchEvent)>;
+
+// Logged once the main menu is up: the sound engine on 1.7+, OpenAL on 1.6 and
+// the block atlas on 1.14+ in case sound is off; the last one is joining a world
+const READY_PATTERNS: [&str; 4] = [
+    "Sound engine started",
+    "OpenAL initialized",
+    "minecraft:textures/atlas/blocks.png-atlas",
+    "joined the game",
+];
+
+pub fn is_ready_line(line: &str) -> bool {
+    READY_PATTERNS.iter().any(|pattern| line.contains(pattern))
+}
+
+// Prints the event stream for the --events flag
+pub fn print_event(event: &LaunchEvent) {
+    match event {
+        LaunchEvent::ResolvingVersion { version } => eprintln!("[event] resolving version {}", version),
+        LaunchEvent::Downloading { name, pct } => eprintln!("[event] downloading {} {}%", name, pct),
+        LaunchEvent::BuildingArguments => eprintln!("[event] building arguments"),
+        LaunchEvent::Spawning => eprintln!("[event] spawning"),
+        LaunchEvent::Spawned { pid } => eprintln!("[event] spawned pid {}", pid),
+        LaunchEvent::Ready { line } => eprintln!("[event] ready: {}", line.trim()),
+        LaunchEvent::Exited { code, outcome } => match code {
+            Some(code) => eprintln!("[event] exited with code {}: {}", code, outcome),
+            None => eprintln!("[event] exited: {}", outcome),
+        },
+    }
+}
@@ -0,0 +1,198 @@
+use std::{
+    fs,
+    io,
+    path::Path,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, to_string_pretty};
+
+use crate::{classpath_cache, download, install};
+
+// Patterns of files a shared manifest should neither list nor demand, one per line
+const IGNORE_FILE_NAME: &str = ".mcignore";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InstanceManifest {
+    pub files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestFile {
+    // Relative to the game dir, always with forward slashes
+    pub path: String,
+    pub sha1: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    pub missing: Vec<String>,
+    pub modified: Vec<String>,
+    pub checked: usize,
+}
+
+pub fn export_manifest(game_dir: &Path) -> io::Result<InstanceManifest> {
+    let ignore = IgnoreList::load(game_dir)?;
+    let mut files = Vec::new();
+
+    collect_files(game_dir, game_dir, &ignore, &mut files)?;
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(InstanceManifest { files })
+}
+
+pub fn verify_against_manifest(game_dir: &Path, manifest: &InstanceManifest) -> io::Result<VerifyReport> {
+    let ignore = IgnoreList::load(game_dir)?;
+    let mut report = VerifyReport::default();
+
+    for file in manifest.files.iter().filter(|f| !ignore.is_ignored(&f.path, false)) {
+        report.checked += 1;
+
+        let path = game_dir.join(&file.path);
+        match fs::metadata(&path) {
+            Ok(metadata) if metadata.len() == file.size && download::sha1_file(&path)? == file.sha1 => {}
+            Ok(_) => report.modified.push(file.path.clone()),
+            Err(_) => report.missing.push(file.path.clone()),
+        }
+    }
+
+    Ok(report)
+}
+
+pub fn read_manifest(path: &Path) -> io::Result<InstanceManifest> {
+    let content = fs::read_to_string(path).map_err(|e| io::Error::new(
+        e.kind(),
+        format!("无法读取文件 {}: {}", path.display(), e)))?;
+
+    from_str(&content).map_err(|e| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("无效的JSON格式 {}: {}", path.display(), e)))
+}
+
+pub fn write_manifest(path: &Path, manifest: &InstanceManifest) -> io::Result<()> {
+    let content = to_string_pretty(manifest).map_err(io::Error::other)?;
+
+    fs::write(path, content)
+}
+
+fn collect_files(
+    game_dir: &Path,
+    dir: &Path,
+    ignore: &IgnoreList,
+    files: &mut Vec<ManifestFile>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let relative = path
+            .strip_prefix(game_dir)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let is_dir = entry.file_type()?.is_dir();
+
+        if ignore.is_ignored(&relative, is_dir) {
+            continue;
+        }
+
+        if is_dir {
+            collect_files(game_dir, &path, ignore, files)?;
+        } else {
+            files.push(ManifestFile {
+                sha1: download::sha1_file(&path)?,
+                size: entry.metadata()?.len(),
+                path: relative,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+// gitignore-like: `*` and `?` stay within one path segment, `**` spans segments,
+// a trailing `/` only matches directories and patterns without `/` match at any depth
+struct IgnoreList {
+    patterns: Vec<String>,
+}
+
+impl IgnoreList {
+    fn load(game_dir: &Path) -> io::Result<IgnoreList> {
+        let content = match fs::read_to_string(game_dir.join(IGNORE_FILE_NAME)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+
+        let patterns = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(String::from)
+            .collect();
+
+        Ok(IgnoreList { patterns })
+    }
+
+    fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        // Leftovers of interrupted downloads, write probes and caches never belong in a manifest
+        if path.ends_with(".part")
+            || path.ends_with(".wmml-write-test")
+            || path.ends_with(install::VERIFIED_SUFFIX)
+            || path.ends_with(classpath_cache::CACHE_FILE_NAME)
+        {
+            return true;
+        }
+
+        self.patterns.iter().any(|pattern| {
+            let (pattern, dir_only) = match pattern.strip_suffix('/') {
+                Some(pattern) => (pattern, true),
+                None => (pattern.as_str(), false),
+            };
+
+            let anchored = pattern.trim_start_matches('/');
+            let matches = if pattern.contains('/') {
+                glob_match(anchored, path)
+            } else {
+                path.rsplit('/').next().is_some_and(|name| glob_match(pattern, name))
+            };
+
+            // A pattern naming a directory also covers everything inside it
+            let inside = path
+                .char_indices()
+                .filter(|(_, c)| *c == '/')
+                .any(|(i, _)| {
+                    let parent = &path[..i];
+                    if pattern.contains('/') {
+                        glob_match(anchored, parent)
+                    } else {
+                        parent.rsplit('/').next().is_some_and(|name| glob_match(pattern, name))
+                    }
+                });
+
+            (matches && (is_dir || !dir_only)) || inside
+        })
+    }
+}
+
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    glob_match_from(&pattern, &text)
+}
+
+fn glob_match_from(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', rest @ ..] => {
+            // "**/" may also match nothing at all
+            let rest = rest.strip_prefix(&['/']).unwrap_or(rest);
+            (0..=text.len()).any(|i| glob_match_from(rest, &text[i..]))
+        }
+        ['*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != '/')
+            .any(|i| glob_match_from(rest, &text[i..])),
+        ['?', rest @ ..] => matches!(text, [c, ..] if *c != '/') && glob_match_from(rest, &text[1..]),
+        [p, rest @ ..] => matches!(text, [c, ..] if c == p) && glob_match_from(rest, &text[1..]),
+    }
+}
@@ -0,0 +1,92 @@
+use std::{
+    fs,
+    io,
+    path::Path,
+};
+use chrono::{DateTime, FixedOffset, NaiveDate};
+
+// The game's own settings, one key:value per line
+const OPTIONS_FILE_NAME: &str = "options.txt";
+
+// A locale such as zh_CN, zh-CN or zh split into language and optional country
+pub fn parse_locale(value: &str) -> Option<(String, Option<String>)> {
+    let mut parts = value.trim().split(['_', '-']);
+    let language = parts.next()?;
+    let country = parts.next();
+
+    if parts.next().is_some()
+        || !(2..=3).contains(&language.len())
+        || !language.chars().all(|c| c.is_ascii_alphabetic())
+    {
+        return None;
+    }
+
+    // Two letters or a three digit UN M.49 area, as Java accepts
+    let country = match country {
+        Some(country) if (country.len() == 2 && country.chars().all(|c| c.is_ascii_alphabetic()))
+            || (country.len() == 3 && country.chars().all(|c| c.is_ascii_digit())) => Some(country.to_ascii_uppercase()),
+        Some(_) => return None,
+        None => None,
+    };
+
+    Some((language.to_ascii_lowercase(), country))
+}
+
+// Versions since 16w32a name languages in lower case (zh_cn), older ones as zh_CN;
+// an unknown release time is taken as a recent version
+pub fn lower_case_language(release_time: Option<DateTime<FixedOffset>>) -> bool {
+    let since = NaiveDate::from_ymd_opt(2016, 8, 10);
+
+    release_time.is_none_or(|release_time| Some(release_time.date_naive()) >= since)
+}
+
+pub fn language_code(language: &str, country: Option<&str>, lower_case: bool) -> String {
+    let code = match country {
+        Some(country) => format!("{}_{}", language, country),
+        None => language.to_string(),
+    };
+
+    if lower_case {
+        code.to_ascii_lowercase()
+    } else {
+        code
+    }
+}
+
+// Replaces the key's line or appends one, keeping every other setting and the line order
+pub fn set_option(game_dir: &Path, key: &str, value: &str) -> io::Result<()> {
+    let path = game_dir.join(OPTIONS_FILE_NAME);
+
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(io::Error::new(
+            e.kind(),
+            format!("无法读取文件 {}: {}", path.display(), e))),
+    };
+
+    let line = format!("{}:{}", key, value);
+    let mut found = false;
+    let mut lines: Vec<String> = content
+        .lines()
+        .map(|existing| {
+            if existing.split_once(':').is_some_and(|(k, _)| k == key) {
+                found = true;
+                line.clone()
+            } else {
+                existing.to_string()
+            }
+        })
+        .collect();
+
+    if !found {
+        lines.push(line);
+    }
+
+    let mut content = lines.join("\n");
+    content.push('\n');
+
+    fs::write(&path, content).map_err(|e| io::Error::new(
+        e.kind(),
+        format!("无法写入文件 {}: {}", path.display(), e)))
+}
@@ -0,0 +1,920 @@
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs,
+    io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::UNIX_EPOCH,
+};
+use serde::Deserialize;
+use serde_json::from_str;
+
+use crate::{
+    check_library_rules, client_jar_path, collect_classpath, download, error::WmmlError, get_library_path, layout::Layout,
+    manifest, platform::TargetPlatform, read_version, resolve_version, version_json_path, VersionJson,
+};
+
+const ASSET_OBJECTS_URL: &str = "https://resources.download.minecraft.net";
+
+// Written last, so its presence means every artifact was downloaded and verified
+pub const INSTALL_MARKER_NAME: &str = ".install-complete";
+// Next to a client jar: "<sha1> <size> <mtime>" of its last successful check
+pub const VERIFIED_SUFFIX: &str = ".wmml-verified";
+
+// The download entries of a version JSON, which VersionJson itself skips
+#[derive(Debug, Deserialize)]
+struct VersionDownloads {
+    downloads: Option<ClientDownloads>,
+    #[serde(default)]
+    libraries: Vec<LibraryDownloads>,
+    #[serde(rename = "assetIndex")]
+    asset_index: Option<RemoteFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClientDownloads {
+    client: RemoteFile,
+    // Dedicated server jar, missing for very old versions and loader profiles
+    server: Option<RemoteFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LibraryDownloads {
+    downloads: Option<LibraryArtifacts>,
+    // Maven repository of loader libraries that come without download entries
+    url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LibraryArtifacts {
+    artifact: Option<RemoteFile>,
+    #[serde(default)]
+    classifiers: HashMap<String, RemoteFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RemoteFile {
+    // Only set for library artifacts, relative to the libraries dir
+    path: Option<String>,
+    sha1: String,
+    size: Option<u64>,
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AssetIndexFile {
+    objects: HashMap<String, AssetObject>,
+    // Versions before 1.7.3 read assets/virtual/<index>/<name> instead of the objects
+    #[serde(default, rename = "virtual")]
+    is_virtual: bool,
+    // Before 1.6 the game reads <game dir>/resources/<name>
+    #[serde(default)]
+    map_to_resources: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct AssetObject {
+    hash: String,
+    size: u64,
+}
+
+pub struct Artifact {
+    pub url: String,
+    pub path: PathBuf,
+    pub sha1: String,
+    pub size: Option<u64>,
+}
+
+// Re-running after an interruption only fetches what is missing or does not verify. Without
+// asset_objects only the asset index is fetched: the game still starts, without sounds and the
+// translations outside its jar, and the install is not marked complete
+pub fn install_version(
+    mc_path: &Path,
+    layout: &Layout,
+    version_id: &str,
+    platform: &TargetPlatform,
+    server_jar: bool,
+    asset_objects: bool,
+    jobs: usize,
+) -> Result<(), WmmlError> {
+    let plan = plan_install(mc_path, layout, version_id, platform, server_jar, asset_objects)?;
+    let total = plan.artifacts.len();
+
+    // None for files already in place, otherwise whether the download worked
+    let results = parallel_map(&plan.artifacts, jobs, |i, artifact| {
+        if is_intact(artifact, plan.check_hash(artifact)) {
+            return None;
+        }
+
+        println!("[{}/{}] Downloading {}", i + 1, total, artifact.path.display());
+        match download::download_file(&artifact.url, &artifact.path, Some(&artifact.sha1)) {
+            Ok(()) => Some(true),
+            Err(e) => {
+                log::warn!("{}", e);
+                Some(false)
+            }
+        }
+    });
+
+    plan.finish(&results)
+}
+
+// What an install still has to download, shared by the blocking and the async installer
+pub struct InstallPlan {
+    pub version_id: String,
+    pub artifacts: Vec<Artifact>,
+    // Hashing every asset again is slow, so completed installs only check sizes
+    check_hashes: bool,
+    versions_dir: PathBuf,
+    marker: PathBuf,
+    // Left out for installs without the asset objects
+    write_marker: bool,
+    assets_dir: PathBuf,
+    // resources/ of the shared game dir; launches fill the one of an isolated game dir
+    resources_dir: PathBuf,
+}
+
+// Fetches the version JSON and the asset index when missing and makes sure the rest fits on the disk
+pub fn plan_install(
+    mc_path: &Path,
+    layout: &Layout,
+    version_id: &str,
+    platform: &TargetPlatform,
+    server_jar: bool,
+    asset_objects: bool,
+) -> Result<InstallPlan, WmmlError> {
+    let marker = layout.version_dir(mc_path, version_id).join(INSTALL_MARKER_NAME);
+    let installed = marker.exists();
+
+    let json_path = version_json_path(mc_path, layout, version_id);
+    if !json_path.exists() {
+        let manifest = manifest::fetch_version_manifest(mc_path)?;
+        let entry = manifest
+            .versions
+            .iter()
+            .find(|v| v.id == version_id)
+            .ok_or_else(|| io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("版本列表中没有 {}", version_id)))?;
+
+        download::download_file(&entry.url, &json_path, entry.sha1.as_deref())?;
+    }
+
+    let mut artifacts = collect_artifacts(mc_path, layout, version_id, true, platform, server_jar)?;
+    if !asset_objects {
+        let objects_dir = layout.assets_dir(mc_path).join("objects");
+        artifacts.retain(|artifact| !artifact.path.starts_with(&objects_dir));
+    }
+    if server_jar && !artifacts.iter().any(|artifact| artifact.path == server_jar_path(mc_path, layout, version_id)) {
+        log::warn!("{} has no server jar to download", version_id);
+    }
+
+    // Running out of space halfway leaves a version that neither launches nor resumes cleanly
+    let required = estimate_download(&artifacts);
+    match available_space(mc_path) {
+        Ok(available) if available < required => {
+            return Err(WmmlError::InsufficientSpace { path: mc_path.to_path_buf(), required, available });
+        }
+        Ok(_) => {}
+        Err(e) => log::warn!("cannot check the free disk space: {}", e),
+    }
+
+    Ok(InstallPlan {
+        version_id: version_id.to_string(),
+        artifacts,
+        check_hashes: !installed,
+        versions_dir: mc_path.join(&layout.versions),
+        marker,
+        write_marker: asset_objects,
+        assets_dir: layout.assets_dir(mc_path),
+        resources_dir: mc_path.join("resources"),
+    })
+}
+
+impl InstallPlan {
+    // The launch check sends users here when a client jar does not match, so the few jars in the
+    // versions dir are hashed even after a completed install
+    pub fn check_hash(&self, artifact: &Artifact) -> bool {
+        self.check_hashes || artifact.path.starts_with(&self.versions_dir)
+    }
+
+    // Takes one result per artifact: None when it was in place, otherwise whether it downloaded
+    pub fn finish(&self, results: &[Option<bool>]) -> Result<(), WmmlError> {
+        let downloaded = results.iter().filter(|result| **result == Some(true)).count();
+        let failed = results.iter().filter(|result| **result == Some(false)).count();
+
+        if failed > 0 {
+            let _ = fs::remove_file(&self.marker);
+            return Err(io::Error::other(format!("{} 个文件下载失败，重新运行安装以继续", failed)).into());
+        }
+
+        if self.write_marker {
+            let indexes_dir = self.assets_dir.join("indexes");
+            if let Some(index) = self.artifacts.iter().find(|artifact| artifact.path.starts_with(&indexes_dir)) {
+                lay_out_legacy_assets(&index.path, &self.assets_dir, &self.resources_dir)?;
+            }
+            fs::write(&self.marker, "")?;
+        }
+        println!(
+            "Installed {}: {} downloaded, {} already present",
+            self.version_id,
+            downloaded,
+            self.artifacts.len() - downloaded
+        );
+
+        Ok(())
+    }
+}
+
+// Old versions do not know the objects store, so their index asks for the assets under their
+// names as well. Hard links cost no space; a copy is made where the file system has none
+pub fn lay_out_legacy_assets(index_path: &Path, assets_dir: &Path, resources_dir: &Path) -> io::Result<()> {
+    let index_file: AssetIndexFile = from_str(&fs::read_to_string(index_path)?).map_err(|e| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("无效的资源索引 {}: {}", index_path.display(), e)))?;
+    let index_id = index_path.file_stem().unwrap_or_default();
+
+    let mut targets = Vec::new();
+    if index_file.is_virtual {
+        targets.push(assets_dir.join("virtual").join(index_id));
+    }
+    if index_file.map_to_resources {
+        targets.push(resources_dir.to_path_buf());
+    }
+    if targets.is_empty() {
+        return Ok(());
+    }
+
+    for (name, object) in &index_file.objects {
+        // The names come from the network and must stay inside the target
+        let relative = Path::new(name);
+        if !relative.components().all(|component| matches!(component, std::path::Component::Normal(_))) {
+            log::warn!("skipping asset {} outside the assets folder", name);
+            continue;
+        }
+
+        let prefix = &object.hash[..2.min(object.hash.len())];
+        let source = assets_dir.join("objects").join(prefix).join(&object.hash);
+
+        for target in &targets {
+            let dest = target.join(relative);
+            if fs::metadata(&dest).is_ok_and(|meta| meta.len() == object.size) {
+                continue;
+            }
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            // Built under a name of its own and renamed over dest, since another install may be
+            // laying out the same index; copying onto a link of the object would truncate it
+            let part_path = download::part_path(&dest);
+            if fs::hard_link(&source, &part_path).is_err() {
+                fs::copy(&source, &part_path).map_err(|e| {
+                    let _ = fs::remove_file(&part_path);
+                    io::Error::new(e.kind(), format!("无法复制资源 {} 到 {}: {}", source.display(), dest.display(), e))
+                })?;
+            }
+            download::finish_part(&part_path, &dest, Some(&object.hash))?;
+            // Renaming onto another link of the same file succeeds without removing the part
+            let _ = fs::remove_file(&part_path);
+        }
+    }
+
+    Ok(())
+}
+
+// Downloads and hashing run on this many threads unless --jobs says otherwise
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(4)
+}
+
+// Runs f over items on up to `jobs` threads and returns the results in the order of items
+pub fn parallel_map<T: Sync, R: Send>(items: &[T], jobs: usize, f: impl Fn(usize, &T) -> R + Sync) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+
+    let mut results: Vec<(usize, R)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs.clamp(1, items.len().max(1)))
+            .map(|_| scope.spawn(|| {
+                let mut results = Vec::new();
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(i) else {
+                        break results;
+                    };
+                    results.push((i, f(i, item)));
+                }
+            }))
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect()
+    });
+
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+// Next to the client jar; the launcher never runs it, it is only fetched for packs that ship a server
+pub fn server_jar_path(mc_path: &Path, layout: &Layout, version_id: &str) -> PathBuf {
+    layout.version_dir(mc_path, version_id).join(format!("{}-server.jar", version_id))
+}
+
+// Bytes still to download; artifacts without a known size count as nothing
+pub fn estimate_download(artifacts: &[Artifact]) -> u64 {
+    artifacts
+        .iter()
+        .filter(|artifact| !is_intact(artifact, false))
+        .filter_map(|artifact| artifact.size)
+        .sum()
+}
+
+#[cfg(unix)]
+fn available_space(path: &Path) -> io::Result<u64> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes()).map_err(io::Error::other)?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+
+    // SAFETY: path is NUL-terminated and stat is a valid out pointer
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(windows)]
+fn available_space(path: &Path) -> io::Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDiskFreeSpaceExW(
+            directory: *const u16,
+            free_to_caller: *mut u64,
+            total: *mut u64,
+            total_free: *mut u64,
+        ) -> i32;
+    }
+
+    let path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut available = 0;
+
+    // SAFETY: path is NUL-terminated and the out pointers are valid or null as allowed
+    if unsafe { GetDiskFreeSpaceExW(path.as_ptr(), &mut available, std::ptr::null_mut(), std::ptr::null_mut()) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(available)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn available_space(_path: &Path) -> io::Result<u64> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+pub fn is_intact(artifact: &Artifact, check_hash: bool) -> bool {
+    let Ok(metadata) = fs::metadata(&artifact.path) else {
+        return false;
+    };
+
+    if artifact.size.is_some_and(|size| size != metadata.len()) {
+        return false;
+    }
+
+    !check_hash || download::sha1_file(&artifact.path).is_ok_and(|sha1| sha1.eq_ignore_ascii_case(&artifact.sha1))
+}
+
+// Checks the client jar against the sha1 in the JSON of the version it belongs to; true
+// when there is nothing to check against. Unchanged jars are not hashed a second time
+pub fn verify_client_jar(mc_path: &Path, layout: &Layout, version_json: &VersionJson) -> io::Result<bool> {
+    let jar_id = version_json.jar.as_deref().unwrap_or(&version_json.id);
+    let json_path = version_json_path(mc_path, layout, jar_id);
+    if !json_path.exists() {
+        return Ok(true);
+    }
+
+    let content = fs::read_to_string(&json_path)?;
+    let Some(client) = from_str::<VersionDownloads>(&content).ok().and_then(|d| d.downloads).map(|d| d.client) else {
+        return Ok(true);
+    };
+
+    let jar_path = client_jar_path(mc_path, layout, version_json);
+    let metadata = fs::metadata(&jar_path)?;
+    let mtime = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let record = format!("{} {} {}", client.sha1.to_ascii_lowercase(), metadata.len(), mtime);
+
+    let mut sidecar = OsString::from(jar_path.as_os_str());
+    sidecar.push(VERIFIED_SUFFIX);
+    let sidecar = PathBuf::from(sidecar);
+
+    if fs::read_to_string(&sidecar).is_ok_and(|recorded| recorded.trim() == record) {
+        return Ok(true);
+    }
+
+    let intact = download::sha1_file(&jar_path)?.eq_ignore_ascii_case(&client.sha1);
+    if intact {
+        // Only a cache; the next launch hashes again if it cannot be written
+        let _ = fs::write(&sidecar, record);
+    } else {
+        let _ = fs::remove_file(&sidecar);
+    }
+
+    Ok(intact)
+}
+
+#[derive(Debug)]
+pub struct LibraryDownload {
+    pub path: PathBuf,
+    pub url: String,
+    // Maven urls come without a hash
+    pub sha1: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct LibraryReport {
+    pub present: Vec<PathBuf>,
+    pub downloadable: Vec<LibraryDownload>,
+    // Neither a download entry nor a maven url says where these come from
+    pub missing: Vec<PathBuf>,
+}
+
+// The classpath libraries of a version for these rules, split by what a download can fix
+pub fn resolve_libraries(
+    mc_path: &Path,
+    layout: &Layout,
+    version_id: &str,
+    platform: &TargetPlatform,
+) -> io::Result<LibraryReport> {
+    let libraries_dir = layout.libraries_dir(mc_path);
+    let mut sources: HashMap<PathBuf, LibraryDownload> = HashMap::new();
+    let mut next = Some(version_id.to_string());
+
+    while let Some(id) = next.take() {
+        let json_path = version_json_path(mc_path, layout, &id);
+
+        // Stripped installs have no JSON and therefore no download entries
+        if json_path.exists() {
+            let content = fs::read_to_string(&json_path).map_err(|e| io::Error::new(
+                e.kind(),
+                format!("无法读取文件 {}: {}", json_path.display(), e)))?;
+            let invalid = |e: serde_json::Error| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("无效的JSON格式 {}: {}", json_path.display(), e));
+            let version_json: VersionJson = from_str(&content).map_err(invalid)?;
+            let downloads: VersionDownloads = from_str(&content).map_err(invalid)?;
+
+            for (lib, lib_downloads) in version_json.libraries.iter().zip(downloads.libraries) {
+                if !check_library_rules(lib, platform) {
+                    continue;
+                }
+
+                let files = lib_downloads
+                    .downloads
+                    .into_iter()
+                    .flat_map(|d| d.artifact.into_iter().chain(d.classifiers.into_values()));
+                for file in files {
+                    if let Some(path) = file.path.as_deref() {
+                        sources.entry(libraries_dir.join(path)).or_insert(LibraryDownload {
+                            path: libraries_dir.join(path),
+                            url: file.url,
+                            sha1: Some(file.sha1),
+                        });
+                    }
+                }
+
+                let Some(base) = lib_downloads.url else {
+                    continue;
+                };
+                let Some(path) = get_library_path(mc_path, layout, lib, platform) else {
+                    continue;
+                };
+                if let Ok(relative) = path.strip_prefix(&libraries_dir) {
+                    let url = format!("{}/{}", base.trim_end_matches('/'), relative.to_string_lossy().replace('\\', "/"));
+                    sources.entry(path.clone()).or_insert(LibraryDownload { path, url, sha1: None });
+                }
+            }
+        }
+
+        next = read_version(mc_path, layout, &id)?.inherits_from;
+    }
+
+    let mut report = LibraryReport::default();
+    let version_json = resolve_version(mc_path, layout, version_id)?;
+
+    // The client jar comes first and is not a library
+    for path in collect_classpath(mc_path, layout, &version_json, platform).into_iter().skip(1) {
+        if path.exists() {
+            report.present.push(path);
+        } else if let Some(download) = sources.remove(&path) {
+            report.downloadable.push(download);
+        } else if !report.missing.contains(&path) {
+            report.missing.push(path);
+        }
+    }
+
+    Ok(report)
+}
+
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    pub checked: usize,
+    pub missing: Vec<PathBuf>,
+    pub corrupt: Vec<PathBuf>,
+}
+
+// Hashes everything the version and the versions it inherits from download, never fetching anything
+pub fn verify_version(mc_path: &Path, layout: &Layout, version_id: &str, jobs: usize) -> io::Result<VerifyReport> {
+    let mut report = VerifyReport::default();
+    let mut artifacts = Vec::new();
+    let mut next = Some(version_id.to_string());
+
+    while let Some(id) = next.take() {
+        // A server jar is only checked when one was installed
+        let server_jar = server_jar_path(mc_path, layout, &id).exists();
+        artifacts.extend(collect_artifacts(mc_path, layout, &id, false, TargetPlatform::host(), server_jar)?);
+
+        next = read_version(mc_path, layout, &id)?.inherits_from;
+    }
+
+    // Some(true) for missing files, Some(false) for corrupt ones
+    let results = parallel_map(&artifacts, jobs, |_, artifact| {
+        if !artifact.path.exists() {
+            Some(true)
+        } else if !is_intact(artifact, true) {
+            Some(false)
+        } else {
+            None
+        }
+    });
+
+    for (artifact, result) in artifacts.into_iter().zip(results) {
+        report.checked += 1;
+
+        match result {
+            Some(true) => report.missing.push(artifact.path),
+            Some(false) => report.corrupt.push(artifact.path),
+            None => {}
+        }
+    }
+
+    // Loader libraries usually come without download entries, so at least make sure they exist
+    let classpath = collect_classpath(mc_path, layout, &resolve_version(mc_path, layout, version_id)?, TargetPlatform::host());
+    for path in classpath {
+        if !path.exists() && !report.missing.contains(&path) {
+            report.checked += 1;
+            report.missing.push(path);
+        }
+    }
+
+    Ok(report)
+}
+
+// Client jar, libraries for this platform, asset index and asset objects; a missing index is fetched if asked
+pub fn collect_artifacts(
+    mc_path: &Path,
+    layout: &Layout,
+    version_id: &str,
+    fetch_index: bool,
+    platform: &TargetPlatform,
+    server_jar: bool,
+) -> io::Result<Vec<Artifact>> {
+    let json_path = version_json_path(mc_path, layout, version_id);
+    let content = fs::read_to_string(&json_path).map_err(|e| io::Error::new(
+        e.kind(),
+        format!("无法读取文件 {}: {}", json_path.display(), e)))?;
+    let invalid = |e: serde_json::Error| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("无效的JSON格式 {}: {}", json_path.display(), e));
+
+    let version_json: VersionJson = from_str(&content).map_err(invalid)?;
+    let downloads: VersionDownloads = from_str(&content).map_err(invalid)?;
+    let mut artifacts = Vec::new();
+
+    if let Some(ClientDownloads { client, server }) = downloads.downloads {
+        artifacts.push(Artifact {
+            path: client_jar_path(mc_path, layout, &version_json),
+            url: client.url,
+            sha1: client.sha1,
+            size: client.size,
+        });
+
+        if let Some(server) = server.filter(|_| server_jar) {
+            artifacts.push(Artifact {
+                path: server_jar_path(mc_path, layout, version_id),
+                url: server.url,
+                sha1: server.sha1,
+                size: server.size,
+            });
+        }
+    }
+
+    let libraries_dir = layout.libraries_dir(mc_path);
+    for (lib, lib_downloads) in version_json.libraries.iter().zip(downloads.libraries) {
+        let Some(mut lib_downloads) = lib_downloads.downloads.filter(|_| check_library_rules(lib, platform)) else {
+            continue;
+        };
+
+        let native = lib.natives.as_ref().and_then(|natives| natives.get(&platform.os)).and_then(|classifier| {
+            let classifier = classifier.replace("${arch}", platform.arch_bits());
+            lib_downloads.classifiers.remove(&classifier)
+        });
+
+        for file in lib_downloads.artifact.into_iter().chain(native) {
+            let Some(path) = file.path.as_deref() else {
+                continue;
+            };
+
+            artifacts.push(Artifact {
+                path: libraries_dir.join(path),
+                url: file.url,
+                sha1: file.sha1,
+                size: file.size,
+            });
+        }
+    }
+
+    if let Some(index) = downloads.asset_index {
+        let assets_dir = layout.assets_dir(mc_path);
+        let index_id = version_json.asset_index.as_ref().map(|a| a.id.as_str()).unwrap_or(version_id);
+        let index_path = assets_dir.join("indexes").join(format!("{}.json", index_id));
+
+        if !index_path.exists() {
+            if !fetch_index {
+                artifacts.push(Artifact { path: index_path, url: index.url, sha1: index.sha1, size: index.size });
+                return Ok(artifacts);
+            }
+            download::download_file(&index.url, &index_path, Some(&index.sha1))?;
+        }
+
+        let index_content = fs::read_to_string(&index_path)?;
+        let index_file: AssetIndexFile = from_str(&index_content).map_err(|e| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("无效的资源索引 {}: {}", index_path.display(), e)))?;
+
+        artifacts.push(Artifact {
+            path: index_path,
+            url: index.url,
+            sha1: index.sha1,
+            size: index.size,
+        });
+
+        // Many names can share one object
+        let mut objects: Vec<AssetObject> = index_file.objects.into_values().collect();
+        objects.sort_by(|a, b| a.hash.cmp(&b.hash));
+        objects.dedup_by(|a, b| a.hash == b.hash);
+
+        for object in objects {
+            let prefix = &object.hash[..2.min(object.hash.len())];
+
+            artifacts.push(Artifact {
+                url: format!("{}/{}/{}", ASSET_OBJECTS_URL, prefix, object.hash),
+                path: assets_dir.join("objects").join(prefix).join(&object.hash),
+                sha1: object.hash,
+                size: Some(object.size),
+            });
+        }
+    }
+
+    Ok(artifacts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha1::{Digest, Sha1};
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        sync::Arc,
+        time::Duration,
+    };
+
+    fn sha1_hex(data: &[u8]) -> String {
+        Sha1::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    // Serves the files over plain HTTP on a free port and returns its base URL. Bodies go out in
+    // small pieces so downloads of the same file overlap
+    fn serve(files: HashMap<String, Vec<u8>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let files = Arc::new(files);
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let files = files.clone();
+                thread::spawn(move || {
+                    let mut reader = BufReader::new(&stream);
+                    let mut request = String::new();
+                    let _ = reader.read_line(&mut request);
+                    let mut header = String::new();
+                    while reader.read_line(&mut header).is_ok_and(|read| read > 2) {
+                        header.clear();
+                    }
+
+                    let mut stream = &stream;
+                    let path = request.split_whitespace().nth(1).unwrap_or("");
+                    let Some(body) = files.get(path) else {
+                        let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+                        return;
+                    };
+                    let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len());
+                    for chunk in body.chunks(8 * 1024) {
+                        if stream.write_all(chunk).is_err() {
+                            return;
+                        }
+                        thread::sleep(Duration::from_millis(2));
+                    }
+                });
+            }
+        });
+
+        base
+    }
+
+    #[test]
+    fn parallel_installs_leave_the_shared_files_intact() {
+        let library: Vec<u8> = (0..256 * 1024).map(|i| (i * 31 % 251) as u8).collect();
+        let object = b"shared sound".to_vec();
+        let object_hash = sha1_hex(&object);
+        // A virtual index, so both installs also lay out the same names over one object
+        let names: Vec<String> = (0..500).map(|i| format!("sound/step/{}.ogg", i)).collect();
+        let objects: Vec<String> = names
+            .iter()
+            .map(|name| format!(r#""{}": {{ "hash": "{}", "size": {} }}"#, name, object_hash, object.len()))
+            .collect();
+        let index = format!(r#"{{ "virtual": true, "objects": {{ {} }} }}"#, objects.join(", ")).into_bytes();
+        let clients = ["client a".as_bytes().to_vec(), "client b".as_bytes().to_vec()];
+
+        let mut files = HashMap::from([
+            ("/library.jar".to_string(), library.clone()),
+            ("/index.json".to_string(), index.clone()),
+        ]);
+        for (i, client) in clients.iter().enumerate() {
+            files.insert(format!("/client-{}.jar", i), client.clone());
+        }
+        let base = serve(files);
+
+        let dir = tempfile::tempdir().unwrap();
+        let layout = Layout::default();
+        let assets_dir = layout.assets_dir(dir.path());
+        seed_object(&assets_dir, &object_hash, "shared sound");
+
+        let version_ids = ["pack-a", "pack-b"];
+        for (i, version_id) in version_ids.iter().enumerate() {
+            let json = format!(
+                r#"{{
+                    "id": "{id}",
+                    "mainClass": "net.minecraft.client.main.Main",
+                    "downloads": {{ "client": {{ "sha1": "{client_sha1}", "size": {client_size}, "url": "{base}/client-{i}.jar" }} }},
+                    "libraries": [{{
+                        "name": "com.example:shared:1.0",
+                        "downloads": {{ "artifact": {{
+                            "path": "com/example/shared/1.0/shared-1.0.jar",
+                            "sha1": "{library_sha1}", "size": {library_size}, "url": "{base}/library.jar"
+                        }} }}
+                    }}],
+                    "assets": "shared",
+                    "assetIndex": {{ "id": "shared", "sha1": "{index_sha1}", "size": {index_size}, "url": "{base}/index.json" }}
+                }}"#,
+                id = version_id,
+                client_sha1 = sha1_hex(&clients[i]),
+                client_size = clients[i].len(),
+                library_sha1 = sha1_hex(&library),
+                library_size = library.len(),
+                index_sha1 = sha1_hex(&index),
+                index_size = index.len(),
+            );
+            let json_path = version_json_path(dir.path(), &layout, version_id);
+            fs::create_dir_all(json_path.parent().unwrap()).unwrap();
+            fs::write(json_path, json).unwrap();
+        }
+
+        let platform = TargetPlatform::host();
+        thread::scope(|scope| {
+            let installs: Vec<_> = version_ids
+                .iter()
+                .map(|version_id| scope.spawn(|| install_version(dir.path(), &layout, version_id, platform, false, true, 4)))
+                .collect();
+            for install in installs {
+                install.join().unwrap().unwrap();
+            }
+        });
+
+        let library_path = layout.libraries_dir(dir.path()).join("com/example/shared/1.0/shared-1.0.jar");
+        assert_eq!(download::sha1_file(&library_path).unwrap(), sha1_hex(&library));
+        assert_eq!(download::sha1_file(&assets_dir.join("indexes").join("shared.json")).unwrap(), sha1_hex(&index));
+        let object_path = assets_dir.join("objects").join(&object_hash[..2]).join(&object_hash);
+        assert_eq!(fs::read(object_path).unwrap(), object);
+        let virtual_dir = assets_dir.join("virtual").join("shared");
+        for name in &names {
+            assert_eq!(fs::read(virtual_dir.join(name)).unwrap(), object, "{}", name);
+        }
+        for (i, version_id) in version_ids.iter().enumerate() {
+            let version_dir = layout.version_dir(dir.path(), version_id);
+            assert_eq!(fs::read(version_dir.join(format!("{}.jar", version_id))).unwrap(), clients[i]);
+            assert!(version_dir.join(INSTALL_MARKER_NAME).exists());
+        }
+
+        // Every partial download was renamed into place or cleaned up
+        let leftovers: Vec<_> = fs::read_dir(library_path.parent().unwrap())
+            .unwrap()
+            .chain(fs::read_dir(assets_dir.join("indexes")).unwrap())
+            .chain(fs::read_dir(virtual_dir.join("sound").join("step")).unwrap())
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".part"))
+            .collect();
+        assert!(leftovers.is_empty(), "{:?}", leftovers);
+    }
+
+    #[test]
+    fn a_corrupt_client_jar_is_fetched_again_after_a_completed_install() {
+        let client = b"good client".to_vec();
+        let base = serve(HashMap::from([("/client.jar".to_string(), client.clone())]));
+        let dir = tempfile::tempdir().unwrap();
+        let layout = Layout::default();
+
+        let json = format!(
+            r#"{{
+                "id": "pack",
+                "mainClass": "net.minecraft.client.main.Main",
+                "downloads": {{ "client": {{ "sha1": "{}", "size": {}, "url": "{}/client.jar" }} }},
+                "libraries": []
+            }}"#,
+            sha1_hex(&client),
+            client.len(),
+            base
+        );
+        let version_dir = layout.version_dir(dir.path(), "pack");
+        fs::create_dir_all(&version_dir).unwrap();
+        fs::write(version_json_path(dir.path(), &layout, "pack"), json).unwrap();
+        // Same size, so only a hash tells it apart
+        fs::write(version_dir.join("pack.jar"), "evil client").unwrap();
+        fs::write(version_dir.join(INSTALL_MARKER_NAME), "").unwrap();
+
+        install_version(dir.path(), &layout, "pack", TargetPlatform::host(), false, true, 1).unwrap();
+
+        assert_eq!(fs::read(version_dir.join("pack.jar")).unwrap(), client);
+    }
+
+    fn seed_object(assets_dir: &Path, hash: &str, content: &str) {
+        let path = assets_dir.join("objects").join(&hash[..2]).join(hash);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn legacy_indexes_get_their_assets_under_their_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let assets_dir = dir.path().join("assets");
+        let resources_dir = dir.path().join("resources");
+        seed_object(&assets_dir, "aa11", "click");
+        seed_object(&assets_dir, "bb22", "lang");
+
+        let index_path = assets_dir.join("indexes").join("pre-1.6.json");
+        fs::create_dir_all(index_path.parent().unwrap()).unwrap();
+        fs::write(&index_path, r#"{
+            "virtual": true,
+            "map_to_resources": true,
+            "objects": {
+                "sound/random/click.ogg": { "hash": "aa11", "size": 5 },
+                "lang/en_US.lang": { "hash": "bb22", "size": 4 },
+                "../escape.txt": { "hash": "aa11", "size": 5 }
+            }
+        }"#).unwrap();
+
+        lay_out_legacy_assets(&index_path, &assets_dir, &resources_dir).unwrap();
+
+        let virtual_dir = assets_dir.join("virtual").join("pre-1.6");
+        assert_eq!(fs::read_to_string(virtual_dir.join("sound/random/click.ogg")).unwrap(), "click");
+        assert_eq!(fs::read_to_string(virtual_dir.join("lang/en_US.lang")).unwrap(), "lang");
+        assert_eq!(fs::read_to_string(resources_dir.join("sound/random/click.ogg")).unwrap(), "click");
+        assert!(!assets_dir.join("virtual").join("escape.txt").exists());
+        assert!(!dir.path().join("escape.txt").exists());
+    }
+
+    #[test]
+    fn modern_indexes_leave_the_objects_where_they_are() {
+        let dir = tempfile::tempdir().unwrap();
+        let assets_dir = dir.path().join("assets");
+        seed_object(&assets_dir, "aa11", "click");
+
+        let index_path = assets_dir.join("indexes").join("17.json");
+        fs::create_dir_all(index_path.parent().unwrap()).unwrap();
+        fs::write(&index_path, r#"{ "objects": { "minecraft/sounds/random/click.ogg": { "hash": "aa11", "size": 5 } } }"#).unwrap();
+
+        lay_out_legacy_assets(&index_path, &assets_dir, &dir.path().join("resources")).unwrap();
+
+        assert!(!assets_dir.join("virtual").exists());
+        assert!(!dir.path().join("resources").exists());
+    }
+}
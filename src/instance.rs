@@ -0,0 +1,74 @@
+use std::{
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::layout::Layout;
+
+// Per-version settings kept in the version dir
+const CONFIG_FILE_NAME: &str = "wmml-instance.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct InstanceConfig {
+    // Used instead of the detected or auto-downloaded Java; --java still wins
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub java_path: Option<String>,
+}
+
+pub fn read_config(mc_path: &Path, layout: &Layout, version_name: &str) -> io::Result<InstanceConfig> {
+    let path = layout.version_dir(mc_path, version_name).join(CONFIG_FILE_NAME);
+
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(InstanceConfig::default()),
+        Err(e) => return Err(io::Error::new(
+            e.kind(),
+            format!("无法读取文件 {}: {}", path.display(), e))),
+    };
+
+    serde_json::from_str(&content).map_err(|e| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("无效的JSON格式 {}: {}", path.display(), e)))
+}
+
+pub fn write_config(mc_path: &Path, layout: &Layout, version_name: &str, config: &InstanceConfig) -> io::Result<()> {
+    let path = layout.version_dir(mc_path, version_name).join(CONFIG_FILE_NAME);
+    let content = serde_json::to_string_pretty(config).map_err(io::Error::other)?;
+
+    fs::write(&path, content).map_err(|e| io::Error::new(
+        e.kind(),
+        format!("无法写入文件 {}: {}", path.display(), e)))
+}
+
+// Saves, config and mods of an isolated instance live in its version dir. Assets, libraries and
+// natives are still passed to the game from the shared stores, so nothing of them goes here
+pub fn prepare_isolated_game_dir(mc_path: &Path, layout: &Layout, version_name: &str) -> io::Result<PathBuf> {
+    let game_dir = layout.version_dir(mc_path, version_name);
+    fs::create_dir_all(&game_dir).map_err(|e| io::Error::new(
+        e.kind(),
+        format!("无法创建游戏目录 {}: {}", game_dir.display(), e)))?;
+
+    Ok(game_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn isolated_game_dir_leaves_the_shared_stores_alone() {
+        let mc_path = tempfile::tempdir().unwrap();
+        let layout = Layout::default();
+        fs::create_dir_all(layout.assets_dir(mc_path.path()).join("objects")).unwrap();
+        fs::create_dir_all(layout.libraries_dir(mc_path.path()).join("org")).unwrap();
+
+        let game_dir = prepare_isolated_game_dir(mc_path.path(), &layout, "1.20.1").unwrap();
+
+        assert_eq!(game_dir, layout.version_dir(mc_path.path(), "1.20.1"));
+        assert!(game_dir.is_dir());
+        assert!(!game_dir.join("assets").exists());
+        assert!(!game_dir.join("libraries").exists());
+    }
+}
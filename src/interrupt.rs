@@ -0,0 +1,200 @@
+use std::{
+    io::{self, Write},
+    process::{Child, Command},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+// What Ctrl-C does while WMML waits for the game
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InterruptPolicy {
+    #[default]
+    Ask,
+    Stop,
+    // Leave the game running and hand it the instance lock
+    Detach,
+}
+
+impl InterruptPolicy {
+    pub fn parse(value: &str) -> Option<InterruptPolicy> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "ask" => Some(InterruptPolicy::Ask),
+            "stop" => Some(InterruptPolicy::Stop),
+            "detach" => Some(InterruptPolicy::Detach),
+            _ => None,
+        }
+    }
+}
+
+// Catches Ctrl-C for as long as it lives; before and after, Ctrl-C ends WMML as usual
+pub struct InterruptGuard(());
+
+impl InterruptGuard {
+    pub fn install() -> InterruptGuard {
+        INTERRUPTED.store(false, Ordering::SeqCst);
+        set_handler(true);
+        InterruptGuard(())
+    }
+}
+
+impl Drop for InterruptGuard {
+    fn drop(&mut self) {
+        set_handler(false);
+    }
+}
+
+// True once per Ctrl-C
+pub fn take() -> bool {
+    INTERRUPTED.swap(false, Ordering::SeqCst)
+}
+
+// Returns true when the game should be left running; otherwise it has been told to stop and
+// the caller keeps waiting for it to exit
+pub fn handle(child: &mut Child, policy: InterruptPolicy) -> io::Result<bool> {
+    let stop = match policy {
+        InterruptPolicy::Stop => true,
+        InterruptPolicy::Detach => false,
+        InterruptPolicy::Ask => {
+            eprint!("Stop Minecraft as well? [Y/n] ");
+            let _ = io::stderr().flush();
+
+            let mut answer = String::new();
+            io::stdin().read_line(&mut answer)?;
+            !answer.trim().eq_ignore_ascii_case("n")
+        }
+    };
+
+    if stop {
+        println!("Stopping Minecraft");
+        stop_game(child)?;
+    } else {
+        println!("Leaving Minecraft running with PID {}", child.id());
+    }
+
+    Ok(!stop)
+}
+
+// The game gets its own process group so the terminal's Ctrl-C only reaches WMML
+#[cfg(unix)]
+pub fn isolate_command(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    command.process_group(0);
+}
+
+#[cfg(windows)]
+pub fn isolate_command(command: &mut Command) {
+    use std::os::windows::process::CommandExt;
+
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+
+    command.creation_flags(CREATE_NEW_PROCESS_GROUP);
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn isolate_command(_command: &mut Command) {}
+
+// SIGTERM to the whole group lets the JVM run its shutdown hooks
+#[cfg(unix)]
+pub fn stop_game(child: &mut Child) -> io::Result<()> {
+    // SAFETY: kill only sends a signal; the group id is the child's own pid after isolate_command
+    if unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGTERM) } != 0 {
+        return child.kill();
+    }
+
+    Ok(())
+}
+
+// Anything java started, such as a crash reporter, goes with it
+#[cfg(windows)]
+pub fn stop_game(child: &mut Child) -> io::Result<()> {
+    let status = Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &child.id().to_string()])
+        .status()?;
+
+    if !status.success() {
+        return child.kill();
+    }
+
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn stop_game(child: &mut Child) -> io::Result<()> {
+    child.kill()
+}
+
+#[cfg(unix)]
+fn set_handler(enabled: bool) {
+    extern "C" fn on_signal(_: libc::c_int) {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+    }
+
+    let handler = if enabled { on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t } else { libc::SIG_DFL };
+
+    // SAFETY: the handler only stores an atomic, which is async-signal-safe
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+    }
+}
+
+#[cfg(windows)]
+fn set_handler(enabled: bool) {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetConsoleCtrlHandler(handler: Option<unsafe extern "system" fn(u32) -> i32>, add: i32) -> i32;
+    }
+
+    const CTRL_C_EVENT: u32 = 0;
+    const CTRL_BREAK_EVENT: u32 = 1;
+
+    unsafe extern "system" fn on_ctrl(event: u32) -> i32 {
+        if event == CTRL_C_EVENT || event == CTRL_BREAK_EVENT {
+            INTERRUPTED.store(true, Ordering::SeqCst);
+            1
+        } else {
+            0
+        }
+    }
+
+    // SAFETY: the handler only stores an atomic and is a plain function living for the whole program
+    unsafe {
+        SetConsoleCtrlHandler(Some(on_ctrl), enabled as i32);
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn set_handler(_enabled: bool) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policies_parse_case_insensitively() {
+        assert_eq!(InterruptPolicy::parse(" Detach "), Some(InterruptPolicy::Detach));
+        assert_eq!(InterruptPolicy::parse("STOP"), Some(InterruptPolicy::Stop));
+        assert_eq!(InterruptPolicy::parse("ask"), Some(InterruptPolicy::Ask));
+        assert_eq!(InterruptPolicy::parse("kill"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stopping_terminates_the_group_of_the_game_itself() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let mut command = Command::new("sh");
+        command.args(["-c", "sleep 30; exit 0"]);
+        isolate_command(&mut command);
+
+        let mut child = command.spawn().unwrap();
+        // SAFETY: getpgid only reads the group of a process
+        let group = unsafe { libc::getpgid(child.id() as libc::pid_t) };
+        stop_game(&mut child).unwrap();
+        let status = child.wait().unwrap();
+
+        assert_eq!(group, child.id() as libc::pid_t);
+        assert_eq!(status.signal(), Some(libc::SIGTERM));
+    }
+}
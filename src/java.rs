@@ -0,0 +1,129 @@
+use std::{
+    env,
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+// Practical heap ceiling of a 32-bit JVM on Windows
+pub const MAX_32BIT_HEAP_MB: u32 = 1536;
+
+#[derive(Debug)]
+pub struct JavaInfo {
+    pub version: String,
+    pub arch: String,
+    pub is_64bit: bool,
+}
+
+pub fn detect_java(java_path: &str) -> io::Result<JavaInfo> {
+    let output = Command::new(java_path)
+        .arg("-XshowSettings:properties")
+        .arg("-version")
+        .output()
+        .map_err(|e| io::Error::new(e.kind(), format!("无法运行 Java {}: {}", java_path, e)))?;
+
+    // Both the settings dump and the version banner go to stderr
+    let text = String::from_utf8_lossy(&output.stderr);
+
+    let version = property(&text, "java.version").unwrap_or_default();
+    let arch = property(&text, "os.arch").unwrap_or_default();
+    let is_64bit = match property(&text, "sun.arch.data.model") {
+        Some(model) => model == "64",
+        None => text.contains("64-Bit"),
+    };
+
+    if version.is_empty() && !output.status.success() {
+        return Err(io::Error::other(format!("无法识别 Java 版本: {}", java_path)));
+    }
+
+    Ok(JavaInfo { version, arch, is_64bit })
+}
+
+// Folder next to the WMML binary for portable distributions
+const BUNDLED_JAVA_DIR: &str = "java";
+
+// System java unless it is missing or older than required, then a bundled java/ if there is one
+pub fn default_java(required_major: Option<u32>) -> String {
+    let system_ok = detect_java("java").is_ok_and(|java| {
+        required_major.is_none_or(|required| major_version(&java.version).is_some_and(|major| major >= required))
+    });
+    if system_ok {
+        return "java".to_string();
+    }
+
+    match bundled_java() {
+        Some(java) => {
+            let java = java.to_string_lossy().into_owned();
+            log::info!("Using bundled Java {}", java);
+            java
+        }
+        None => "java".to_string(),
+    }
+}
+
+fn bundled_java() -> Option<PathBuf> {
+    let dir = env::current_exe().ok()?.parent()?.join(BUNDLED_JAVA_DIR);
+    let exe = if cfg!(windows) { "java.exe" } else { "java" };
+
+    [dir.join("bin").join(exe), dir.join(exe)].into_iter().find(|path| path.is_file())
+}
+
+// The executable to run for a --java that may also name a JDK or JRE home. Only Windows has
+// javaw, the same runtime without a console window; it is taken from next to java when asked for
+pub fn resolve_java(java: &str, windowless: bool) -> String {
+    let names: &[&str] = match (cfg!(windows), windowless) {
+        (true, true) => &["javaw.exe", "java.exe"],
+        (true, false) => &["java.exe"],
+        _ => &["java"],
+    };
+    let path = Path::new(java);
+
+    if path.is_dir() {
+        // macOS runtimes keep their home inside a bundle
+        let found = [path.to_path_buf(), path.join("Contents").join("Home")]
+            .iter()
+            .flat_map(|home| names.iter().map(move |name| home.join("bin").join(name)))
+            .find(|candidate| candidate.is_file());
+        return match found {
+            Some(found) => found.to_string_lossy().into_owned(),
+            None => {
+                log::warn!("no java executable under {}", path.display());
+                java.to_string()
+            }
+        };
+    }
+
+    let is_java = path.file_stem().is_some_and(|stem| stem.eq_ignore_ascii_case("java"));
+    if !(cfg!(windows) && windowless && is_java) {
+        return java.to_string();
+    }
+
+    // A bare java comes from PATH, where javaw sits next to it
+    match path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        None => "javaw".to_string(),
+        Some(parent) if parent.join("javaw.exe").is_file() => parent.join("javaw.exe").to_string_lossy().into_owned(),
+        Some(_) => {
+            log::warn!("no javaw.exe next to {}, the game gets a console", java);
+            java.to_string()
+        }
+    }
+}
+
+// 1.8.0_392 is Java 8, 17.0.9 is Java 17
+pub fn major_version(version: &str) -> Option<u32> {
+    let mut parts = version.split(['.', '_', '-', '+']);
+    let first = parts.next()?.parse().ok()?;
+
+    if first == 1 {
+        parts.next()?.parse().ok()
+    } else {
+        Some(first)
+    }
+}
+
+fn property(text: &str, key: &str) -> Option<String> {
+    text.lines().find_map(|line| {
+        let (name, value) = line.split_once('=')?;
+        (name.trim() == key).then(|| value.trim().to_string())
+    })
+}
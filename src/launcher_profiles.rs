@@ -0,0 +1,82 @@
+use std::{
+    fs,
+    io,
+    path::Path,
+    time::SystemTime,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Map, Value};
+
+use crate::{layout::Layout, versions};
+
+const PROFILES_FILE_NAME: &str = "launcher_profiles.json";
+// Format the current official launcher writes; older files keep their own number
+const PROFILES_FORMAT_VERSION: u32 = 3;
+
+// Adds a profile for every installed version the official launcher does not list yet and
+// returns how many were added. Everything else in an existing file is kept as is, since
+// launcher versions put settings, accounts and profiles in different places. Accounts are
+// not exported: the official launcher only takes Microsoft logins done by itself
+pub fn export_launcher_profiles(mc_path: &Path, layout: &Layout) -> io::Result<usize> {
+    if layout.versions != Path::new("versions") {
+        log::warn!(
+            "the official launcher only looks in versions/, versions in {} will not show up there",
+            layout.versions.display()
+        );
+    }
+
+    let path = mc_path.join(PROFILES_FILE_NAME);
+    let mut root = match fs::read_to_string(&path) {
+        // A file we cannot parse is left alone rather than replaced
+        Ok(content) => serde_json::from_str(&content).map_err(|e| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("无效的JSON格式 {}: {}", path.display(), e)))?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            json!({ "profiles": {}, "settings": {}, "version": PROFILES_FORMAT_VERSION })
+        }
+        Err(e) => return Err(e),
+    };
+
+    let Some(root) = root.as_object_mut() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} 的内容不是对象", path.display())));
+    };
+    let profiles = root.entry("profiles").or_insert_with(|| Value::Object(Map::new()));
+    let Some(profiles) = profiles.as_object_mut() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} 中的 profiles 不是对象", path.display())));
+    };
+
+    let now = DateTime::<Utc>::from(SystemTime::now()).to_rfc3339_opts(SecondsFormat::Millis, true);
+    let mut added = 0;
+
+    for version in versions::list_versions(mc_path, layout)? {
+        // Old launchers only had the profile name, which was also the key
+        let listed = profiles.iter().any(|(key, profile)| {
+            profile.get("lastVersionId").and_then(Value::as_str) == Some(&version.id)
+                || (profile.get("lastVersionId").is_none() && key == &version.id)
+        });
+        if listed {
+            continue;
+        }
+
+        profiles.insert(format!("wmml-{}", version.id), json!({
+            "name": version.id,
+            "type": "custom",
+            "lastVersionId": version.id,
+            "created": now,
+            "lastUsed": now,
+            "icon": "Furnace",
+        }));
+        added += 1;
+    }
+
+    let content = serde_json::to_string_pretty(root).map_err(io::Error::other)?;
+    fs::write(&path, content).map_err(|e| io::Error::new(
+        e.kind(),
+        format!("无法写入文件 {}: {}", path.display(), e)))?;
+
+    Ok(added)
+}
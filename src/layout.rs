@@ -0,0 +1,45 @@
+use std::path::{Path, PathBuf};
+
+use crate::platform::TargetPlatform;
+
+// Where versions, libraries and assets live relative to mc_path; absolute paths point outside it
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub versions: PathBuf,
+    pub libraries: PathBuf,
+    pub assets: PathBuf,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout {
+            versions: PathBuf::from("versions"),
+            libraries: PathBuf::from("libraries"),
+            assets: PathBuf::from("assets"),
+        }
+    }
+}
+
+impl Layout {
+    pub fn version_dir(&self, mc_path: &Path, version_name: &str) -> PathBuf {
+        mc_path.join(&self.versions).join(version_name)
+    }
+
+    pub fn libraries_dir(&self, mc_path: &Path) -> PathBuf {
+        mc_path.join(&self.libraries)
+    }
+
+    pub fn assets_dir(&self, mc_path: &Path) -> PathBuf {
+        mc_path.join(&self.assets)
+    }
+}
+
+// natives-windows-x86_64 and the like, one per platform so none overwrites another's
+pub fn natives_dir_name(target: &TargetPlatform) -> String {
+    format!("natives-{}-{}", target.os, target.arch)
+}
+
+// Where a launch without --natives-dir puts and loads the version's natives
+pub fn natives_dir_for(mc_path: &Path, layout: &Layout, version_name: &str, target: &TargetPlatform) -> PathBuf {
+    layout.version_dir(mc_path, version_name).join(natives_dir_name(target))
+}
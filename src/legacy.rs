@@ -0,0 +1,79 @@
+use std::{
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+
+// Entry point of every client before the 1.6 launcher rewrite
+pub const MAIN_CLASS: &str = "net.minecraft.client.Minecraft";
+
+// Pre-1.6 installs keep the game in bin/ instead of versions/<id>/
+pub fn is_legacy_layout(mc_path: &Path) -> bool {
+    mc_path.join("bin").join("minecraft.jar").is_file()
+}
+
+// Legacy clients find their folder on their own: %APPDATA%\.minecraft on Windows,
+// ~/Library/Application Support/minecraft on macOS and ~/.minecraft elsewhere
+pub fn folder_path(os: &str) -> &'static Path {
+    Path::new(match os {
+        "osx" => "Library/Application Support/minecraft",
+        _ => ".minecraft",
+    })
+}
+
+// The APPDATA or user.home that makes the client find mc_path, None when mc_path has the wrong name
+pub fn home_for(mc_path: &Path, os: &str) -> Option<PathBuf> {
+    let folder = folder_path(os);
+    if !mc_path.ends_with(folder) {
+        return None;
+    }
+
+    let mut home = mc_path;
+    for _ in folder.components() {
+        home = home.parent()?;
+    }
+    Some(home.to_path_buf())
+}
+
+pub fn natives_dir(mc_path: &Path) -> PathBuf {
+    mc_path.join("bin").join("natives")
+}
+
+// minecraft.jar goes first, followed by lwjgl.jar, lwjgl_util.jar, jinput.jar and anything else in bin/
+pub fn classpath(mc_path: &Path) -> io::Result<Vec<PathBuf>> {
+    let bin_dir = mc_path.join("bin");
+    let mut jars = Vec::new();
+
+    for entry in fs::read_dir(&bin_dir).map_err(|e| io::Error::new(
+        e.kind(),
+        format!("无法读取目录 {}: {}", bin_dir.display(), e)))?
+    {
+        let path = entry?.path();
+        let is_jar = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("jar"));
+
+        if is_jar && path.file_name().is_some_and(|name| name != "minecraft.jar") {
+            jars.push(path);
+        }
+    }
+
+    jars.sort();
+    jars.insert(0, bin_dir.join("minecraft.jar"));
+
+    Ok(jars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_home_is_where_each_os_looks_for_the_folder() {
+        assert_eq!(home_for(Path::new("/srv/class/.minecraft"), "linux"), Some(PathBuf::from("/srv/class")));
+        assert_eq!(
+            home_for(Path::new("/Users/s/Library/Application Support/minecraft"), "osx"),
+            Some(PathBuf::from("/Users/s"))
+        );
+        assert_eq!(home_for(Path::new("/srv/class/.minecraft"), "osx"), None);
+        assert_eq!(home_for(Path::new("/srv/class/game"), "linux"), None);
+    }
+}
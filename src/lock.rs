@@ -0,0 +1,115 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{self, Command, Stdio},
+};
+
+use crate::error::WmmlError;
+
+const LOCK_FILE_NAME: &str = ".wmml.lock";
+
+// Holds the PID of whoever runs the game dir; a lock whose process is gone is stale and taken over
+pub struct InstanceLock {
+    path: PathBuf,
+}
+
+impl InstanceLock {
+    pub fn acquire(game_dir: &Path, force: bool) -> Result<InstanceLock, WmmlError> {
+        let path = game_dir.join(LOCK_FILE_NAME);
+
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    write!(file, "{}", process::id())?;
+                    return Ok(InstanceLock { path });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    let holder = fs::read_to_string(&path).ok().and_then(|pid| pid.trim().parse().ok());
+
+                    match holder {
+                        Some(pid) if !force && is_running(pid) => return Err(WmmlError::AlreadyRunning(pid)),
+                        _ => fs::remove_file(&path)?,
+                    }
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    // The game outlives the lock guard when WMML does not wait for it
+    pub fn hand_over(self, pid: u32) -> io::Result<()> {
+        fs::write(&self.path, pid.to_string())?;
+        std::mem::forget(self);
+        Ok(())
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn is_running(pid: u32) -> bool {
+    if cfg!(windows) {
+        Command::new("tasklist")
+            .args(["/NH", "/FI", &format!("PID eq {}", pid)])
+            .output()
+            .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+    } else {
+        Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holder(game_dir: &Path) -> String {
+        fs::read_to_string(game_dir.join(LOCK_FILE_NAME)).unwrap()
+    }
+
+    #[test]
+    fn a_running_holder_keeps_the_game_dir_unless_forced() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = InstanceLock::acquire(dir.path(), false).unwrap();
+        assert_eq!(holder(dir.path()), process::id().to_string());
+
+        assert!(matches!(InstanceLock::acquire(dir.path(), false), Err(WmmlError::AlreadyRunning(pid)) if pid == process::id()));
+
+        std::mem::forget(lock);
+        let forced = InstanceLock::acquire(dir.path(), true).unwrap();
+        drop(forced);
+        assert!(!dir.path().join(LOCK_FILE_NAME).exists());
+    }
+
+    #[test]
+    fn a_lock_left_by_an_exited_process_is_taken_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut exited = Command::new(if cfg!(windows) { "cmd" } else { "true" })
+            .args(if cfg!(windows) { &["/C", "exit"][..] } else { &[][..] })
+            .spawn()
+            .unwrap();
+        exited.wait().unwrap();
+        fs::write(dir.path().join(LOCK_FILE_NAME), exited.id().to_string()).unwrap();
+
+        let _lock = InstanceLock::acquire(dir.path(), false).unwrap();
+
+        assert_eq!(holder(dir.path()), process::id().to_string());
+    }
+
+    #[test]
+    fn a_handed_over_lock_names_the_game_and_outlives_the_guard() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = InstanceLock::acquire(dir.path(), false).unwrap();
+
+        lock.hand_over(4242).unwrap();
+
+        assert_eq!(holder(dir.path()), "4242");
+    }
+}
@@ -0,0 +1,185 @@
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+// WMML_LOG names the level written to wmml-logs/, e.g. "info" or "debug"; unset writes no file
+const LOG_ENV: &str = "WMML_LOG";
+const LOG_DIR: &str = "wmml-logs";
+const MAX_LOG_SIZE: u64 = 5 * 1024 * 1024;
+const MAX_LOG_FILES: usize = 10;
+
+struct Logger {
+    file_level: LevelFilter,
+    file: Option<Mutex<LogFile>>,
+}
+
+struct LogFile {
+    dir: PathBuf,
+    date: String,
+    file: File,
+    size: u64,
+}
+
+// Console output stays as before: info on stdout, warnings and errors on stderr
+pub fn init() {
+    let file_level = match env::var(LOG_ENV) {
+        Ok(value) => value.trim().parse().unwrap_or_else(|_| {
+            eprintln!("Warning: unknown {} value {}, using info", LOG_ENV, value);
+            LevelFilter::Info
+        }),
+        Err(_) => LevelFilter::Off,
+    };
+
+    let file = if file_level == LevelFilter::Off {
+        None
+    } else {
+        match LogFile::open(Path::new(LOG_DIR)) {
+            Ok(file) => Some(Mutex::new(file)),
+            Err(e) => {
+                eprintln!("Warning: cannot write launcher log to {}: {}", LOG_DIR, e);
+                None
+            }
+        }
+    };
+
+    if log::set_logger(Box::leak(Box::new(Logger { file_level, file }))).is_ok() {
+        log::set_max_level(file_level.max(LevelFilter::Info));
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Info || (self.file.is_some() && metadata.level() <= self.file_level)
+    }
+
+    fn log(&self, record: &Record) {
+        match record.level() {
+            Level::Error => eprintln!("{}", record.args()),
+            Level::Warn => eprintln!("Warning: {}", record.args()),
+            Level::Info => println!("{}", record.args()),
+            Level::Debug | Level::Trace => {}
+        }
+
+        if let (Some(file), true) = (&self.file, record.level() <= self.file_level) {
+            if let Ok(mut file) = file.lock() {
+                // A broken log file must never take the launcher down
+                let _ = file.write_record(record);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(Ok(mut file)) = self.file.as_ref().map(Mutex::lock) {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+impl LogFile {
+    fn open(dir: &Path) -> io::Result<LogFile> {
+        fs::create_dir_all(dir)?;
+
+        let date = civil_date(unix_seconds() / 86400);
+        let path = dir.join(format!("wmml-{}.log", date));
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+
+        let mut log_file = LogFile { dir: dir.to_path_buf(), date, file, size };
+        if log_file.size >= MAX_LOG_SIZE {
+            log_file.rotate()?;
+        }
+
+        Ok(log_file)
+    }
+
+    fn write_record(&mut self, record: &Record) -> io::Result<()> {
+        let now = unix_seconds();
+        if civil_date(now / 86400) != self.date || self.size >= MAX_LOG_SIZE {
+            self.rotate()?;
+        }
+
+        let line = format!(
+            "[{} {:02}:{:02}:{:02}] [{}] {}: {}\n",
+            self.date,
+            now / 3600 % 24,
+            now / 60 % 60,
+            now % 60,
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+
+        Ok(())
+    }
+
+    // Full files move aside as wmml-<date>.<n>.log; a new day starts a new file
+    fn rotate(&mut self) -> io::Result<()> {
+        let current = self.dir.join(format!("wmml-{}.log", self.date));
+
+        if self.size >= MAX_LOG_SIZE {
+            let rotated = (1..)
+                .map(|n| self.dir.join(format!("wmml-{}.{}.log", self.date, n)))
+                .find(|path| !path.exists())
+                .unwrap_or_else(|| current.with_extension("old.log"));
+            fs::rename(&current, rotated)?;
+        }
+
+        self.date = civil_date(unix_seconds() / 86400);
+        let path = self.dir.join(format!("wmml-{}.log", self.date));
+        self.file = OpenOptions::new().create(true).append(true).open(&path)?;
+        self.size = self.file.metadata()?.len();
+
+        prune_logs(&self.dir, &path)
+    }
+}
+
+// Oldest first thanks to the date in the name; the file being written is always kept
+fn prune_logs(dir: &Path, current: &Path) -> io::Result<()> {
+    let mut logs: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("wmml-") && name.ends_with(".log"))
+        })
+        .filter(|path| path != current)
+        .collect();
+    logs.sort();
+
+    let excess = (logs.len() + 1).saturating_sub(MAX_LOG_FILES);
+    for path in logs.into_iter().take(excess) {
+        fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
+
+fn unix_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// Days since 1970-01-01 to a UTC YYYY-MM-DD, after Howard Hinnant's civil_from_days
+fn civil_date(days: u64) -> String {
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
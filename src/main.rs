@@ -1,399 +1,3786 @@
-use std::{
-    path::{Path, PathBuf},
-    process::{Command, Stdio},
-    collections::HashMap,
-    fs,
-    io::{self, Write},
-    env,
-};
-use serde::{Deserialize, Serialize};
-use serde_json::{Value, from_str};
-
-#[derive(Debug, Serialize, Deserialize)]
-struct VersionJson {
-    id: String,
-    #[serde(rename = "mainClass")]
-    main_class: String,
-    #[serde(rename = "minecraftArguments")]
-    minecraft_arguments: Option<String>,
-    arguments: Option<Arguments>,
-    libraries: Vec<Library>,
-    assets: Option<String>,
-    #[serde(rename = "type")]
-    version_type: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct Arguments {
-    game: Vec<GameArgument>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(untagged)]
-enum GameArgument {
-    String(String),
-    Object(HashMap<String, Value>),
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct Library {
-    name: String,
-    rules: Option<Vec<Rule>>,
-    natives: Option<HashMap<String, String>>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct Rule {
-    action: String,
-    os: Option<Os>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct Os {
-    name: Option<String>,
-    arch: Option<String>,
-}
-
-struct LaunchOptions {
-    java_path: String,
-    memory: Option<u32>,
-    use_system_memory: bool,
-}
-
-fn main() {
-    let mc_path = ".minecraft";
-    let version_name = "1.20.1";
-    let player_name = "Player123";
-
-    let options = LaunchOptions {
-        java_path: "java".to_string(),
-        memory: Some(4096),
-        use_system_memory: false,
-    };
-
-    if let Err(e) = launch_minecraft(mc_path, version_name, player_name, &options) {
-        eprintln!("Failed to launch Minecraft: {}", e);
-    }
-}
-
-fn launch_minecraft(
-    mc_path: &str,
-    version_name: &str,
-    player_name: &str,
-    options: &LaunchOptions,
-) -> io::Result<()> {
-    // Normalize path
-    let mc_path = normalize_path(mc_path)?;
-
-    // Read version JSON file
-    let version_json_path = mc_path
-        .join("versions")
-        .join(version_name)
-        .join(format!("{}.json", version_name));
-    let version_json = read_version_json(&version_json_path)?;
-
-    // Build libraries path
-    let libraries = build_libraries_path(&mc_path, &version_json)?;
-
-    // Build game arguments
-    let game_args = build_game_arguments(&mc_path, version_name, player_name, &version_json);
-
-    // Build Java command
-    let java_command = build_java_command(
-        &mc_path,
-        version_name,
-        &version_json.main_class,
-        &libraries,
-        &game_args,
-        options,
-    );
-
-    println!("Launching Minecraft with command: {}", java_command);
-
-    // Execute command
-    let mut child = Command::new("cmd")
-        .arg("/K")
-        .arg(&java_command)
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .spawn()?;
-
-    println!("Minecraft launched with PID: {}", child.id());
-
-    Ok(())
-}
-
-fn normalize_path(mc_path: &str) -> io::Result<PathBuf> {
-    let path = mc_path.replace('/', "\\");
-    /*if path == ".minecraft" {
-        let current_dir = ".minecarft"; //env::current_dir()?;  // 获取当前工作目录
-        Ok(current_dir.join(".minecraft"))
-    } else {*/
-        Ok(PathBuf::from(path))
-    //}
-}
-
-fn read_version_json(path: &Path) -> io::Result<VersionJson> {
-    let content = fs::read_to_string(path)
-        .map_err(|e| io::Error::new(io::ErrorKind::NotFound, 
-            format!("无法读取文件 {}: {}", path.display(), e)))?;
-    
-    from_str(&content).map_err(|e| io::Error::new(
-        io::ErrorKind::InvalidData, 
-        format!("无效的JSON格式 {}: {}", path.display(), e)))
-}
-
-fn build_libraries_path(mc_path: &Path, version_json: &VersionJson) -> io::Result<String> {
-    let mut libraries = vec![mc_path
-        .join("versions")
-        .join(&version_json.id)
-        .join(format!("{}.jar", version_json.id))];
-
-    for lib in &version_json.libraries {
-        if !check_library_rules(lib) {
-            continue;
-        }
-
-        if let Some(lib_path) = get_library_path(mc_path, lib) {
-            libraries.push(lib_path);
-        }
-    }
-
-    Ok(libraries
-        .into_iter()
-        .map(|p| p.to_string_lossy().into_owned())
-        .collect::<Vec<_>>()
-        .join(";"))
-}
-
-fn check_library_rules(lib: &Library) -> bool {
-    if lib.rules.is_none() || lib.rules.as_ref().unwrap().is_empty() {
-        return true;
-    }
-
-    let os_name = "windows";
-    let os_arch = if cfg!(target_arch = "x86_64") {
-        "x86_64"
-    } else {
-        "x86"
-    };
-
-    let mut should_include = true;
-
-    for rule in lib.rules.as_ref().unwrap() {
-        if rule.action == "allow" {
-            if rule.os.is_none() {
-                should_include = true;
-                continue;
-            }
-
-            let os = rule.os.as_ref().unwrap();
-            if os.name.as_deref() == Some(os_name) {
-                if let Some(arch) = &os.arch {
-                    should_include = arch == os_arch;
-                } else {
-                    should_include = true;
-                }
-            } else {
-                should_include = false;
-            }
-        } else if rule.action == "disallow" {
-            if rule.os.is_none() {
-                should_include = false;
-                continue;
-            }
-
-            if rule.os.as_ref().unwrap().name.as_deref() == Some(os_name) {
-                should_include = false;
-            }
-        }
-    }
-
-    should_include
-}
-
-fn get_library_path(mc_path: &Path, lib: &Library) -> Option<PathBuf> {
-    let parts: Vec<&str> = lib.name.split(':').collect();
-    if parts.len() < 3 {
-        return None;
-    }
-
-    let group_path = parts[0].replace('.', &std::path::MAIN_SEPARATOR.to_string());
-    let artifact_id = parts[1];
-    let version = parts[2];
-
-    let base_path = mc_path
-        .join("libraries")
-        .join(group_path)
-        .join(artifact_id)
-        .join(version);
-    let base_file = format!("{}-{}", artifact_id, version);
-
-    // Check for natives
-    if let Some(natives) = &lib.natives {
-        if let Some(windows_native) = natives.get("windows") {
-            let classifier = windows_native.replace("${arch}", if cfg!(target_arch = "x86_64") { "64" } else { "32" });
-            let native_path = base_path.join(format!("{}-{}.jar", base_file, classifier));
-
-            if native_path.exists() {
-                return Some(native_path);
-            }
-        }
-    }
-
-    // Default to regular jar
-    let jar_path = base_path.join(format!("{}.jar", base_file));
-    if jar_path.exists() {
-        return Some(jar_path);
-    }
-
-    None
-}
-
-fn build_game_arguments(
-    mc_path: &Path,
-    version_name: &str,
-    player_name: &str,
-    version_json: &VersionJson,
-) -> String {
-    let assets_path = mc_path.join("assets");
-    let assets_index = version_json.assets.as_deref().unwrap_or("");
-
-    let mut args = String::new();
-
-    // Handle older versions with minecraftArguments
-    if let Some(minecraft_args) = &version_json.minecraft_arguments {
-        args.push_str(minecraft_args);
-    }
-
-    // Handle newer versions with arguments.game
-    if let Some(arguments) = &version_json.arguments {
-        for arg in &arguments.game {
-            if let GameArgument::String(s) = arg {
-                args.push(' ');
-                args.push_str(s);
-            }
-        }
-    }
-
-    // Replace placeholders
-    let replacements = [
-        ("${auth_player_name}", player_name),
-        ("${version_name}", version_name),
-        ("${game_directory}", mc_path.to_str().unwrap_or("")),
-        ("${assets_root}", assets_path.to_str().unwrap_or("")),
-        ("${assets_index_name}", assets_index),
-        ("${auth_uuid}", "00000000-0000-0000-0000-000000000000"),
-        ("${auth_access_token}", "00000000000000000000000000000000"),
-        ("${user_type}", "legacy"),
-        ("${version_type}", "WMML 0.1.26"),
-    ];
-
-    for (placeholder, value) in replacements {
-        args = args.replace(placeholder, value);
-    }
-
-    args.trim().to_string()
-}
-
-fn build_java_command(
-    mc_path: &Path,
-    version_name: &str,
-    main_class: &str,
-    libraries: &str,
-    game_args: &str,
-    options: &LaunchOptions,
-) -> String {
-    // Memory settings
-    let memory_settings = if !options.use_system_memory && options.memory.is_some() {
-        format!("-Xmx{}M -Xms{}M ", options.memory.unwrap(), options.memory.unwrap())
-    } else {
-        String::new()
-    };
-
-    // Common JVM arguments
-    let common_args = [
-        "-Dfile.encoding=GB18030",
-        "-Dsun.stdout.encoding=GB18030",
-        "-Dsun.stderr.encoding=GB18030",
-        "-Djava.rmi.server.useCodebaseOnly=true",
-        "-Dcom.sun.jndi.rmi.object.trustURLCodebase=false",
-        "-Dcom.sun.jndi.cosnaming.object.trustURLCodebase=false",
-        "-Dlog4j2.formatMsgNoLookups=true",
-        &format!(
-            "-Dlog4j.configurationFile={}",
-            mc_path
-                .join("versions")
-                .join(version_name)
-                .join("log4j2.xml")
-                .to_str()
-                .unwrap_or("")
-        ),
-        &format!(
-            "-Dminecraft.client.jar={}",
-            mc_path
-                .join("versions")
-                .join(version_name)
-                .join(format!("{}.jar", version_name))
-                .to_str()
-                .unwrap_or("")
-        ),
-        "-XX:+UnlockExperimentalVMOptions",
-        "-XX:+UseG1GC",
-        "-XX:G1NewSizePercent=20",
-        "-XX:G1ReservePercent=20",
-        "-XX:MaxGCPauseMillis=50",
-        "-XX:G1HeapRegionSize=32m",
-        "-XX:-UseAdaptiveSizePolicy",
-        "-XX:-OmitStackTraceInFastThrow",
-        "-XX:-DontCompileHugeMethods",
-        "-Dfml.ignoreInvalidMinecraftCertificates=true",
-        "-Dfml.ignorePatchDiscrepancies=true",
-        "-XX:HeapDumpPath=MojangTricksIntelDriversForPerformance_javaw.exe_minecraft.exe.heapdump",
-        &format!(
-            "-Djava.library.path={}",
-            mc_path
-                .join("versions")
-                .join(version_name)
-                .join("natives-windows-x86_64")
-                .to_str()
-                .unwrap_or("")
-        ),
-        &format!(
-            "-Djna.tmpdir={}",
-            mc_path
-                .join("versions")
-                .join(version_name)
-                .join("natives-windows-x86_64")
-                .to_str()
-                .unwrap_or("")
-        ),
-        &format!(
-            "-Dorg.lwjgl.system.SharedLibraryExtractPath={}",
-            mc_path
-                .join("versions")
-                .join(version_name)
-                .join("natives-windows-x86_64")
-                .to_str()
-                .unwrap_or("")
-        ),
-        &format!(
-            "-Dio.netty.native.workdir={}",
-            mc_path
-                .join("versions")
-                .join(version_name)
-                .join("natives-windows-x86_64")
-                .to_str()
-                .unwrap_or("")
-        ),
-        "-Dminecraft.launcher.brand=WMML",
-        "-Dminecraft.launcher.version=0.1.26",
-    ]
-    .join(" ");
-
-    // Construct full command
-    format!(
-        "{} {} {} -cp {} {} {}",
-        options.java_path, memory_settings, common_args, libraries, main_class, game_args
-    )
-}
\ No newline at end of file
+mod classpath_cache;
+mod doctor;
+mod download;
+#[cfg(feature = "async")]
+mod download_async;
+mod error;
+mod events;
+mod export;
+mod game_options;
+mod install;
+mod instance;
+mod interrupt;
+mod java;
+mod launcher_profiles;
+mod layout;
+mod legacy;
+mod lock;
+mod logger;
+mod manifest;
+mod memory;
+mod mirror;
+mod offline;
+mod optifine;
+mod mods;
+mod mrpack;
+mod nbt;
+mod platform;
+mod profile;
+mod runtime;
+mod servers;
+mod session;
+mod shell;
+mod srv;
+mod tail;
+mod versions;
+
+use std::{
+    path::{self, Path, PathBuf},
+    process::{self, Command, ExitStatus, Stdio},
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    env,
+    iter,
+    sync::mpsc::{self, RecvTimeoutError, Sender},
+    thread,
+    time::Duration,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, from_str};
+use chrono::{DateTime, FixedOffset};
+use error::WmmlError;
+use events::{EventHandler, LaunchEvent};
+use interrupt::InterruptPolicy;
+use session::{SessionOutcome, SessionWatch};
+use layout::Layout;
+use platform::TargetPlatform;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct VersionJson {
+    id: String,
+    #[serde(rename = "mainClass")]
+    main_class: String,
+    #[serde(rename = "minecraftArguments", skip_serializing_if = "Option::is_none")]
+    minecraft_arguments: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    arguments: Option<Arguments>,
+    libraries: Vec<Library>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    assets: Option<String>,
+    #[serde(rename = "assetIndex", skip_serializing_if = "Option::is_none")]
+    asset_index: Option<AssetIndex>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    version_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logging: Option<Logging>,
+    #[serde(rename = "javaVersion", skip_serializing_if = "Option::is_none")]
+    java_version: Option<JavaVersion>,
+    #[serde(rename = "inheritsFrom", skip_serializing_if = "Option::is_none")]
+    inherits_from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    jar: Option<String>,
+    #[serde(rename = "minimumLauncherVersion", skip_serializing_if = "Option::is_none")]
+    minimum_launcher_version: Option<u32>,
+    #[serde(
+        rename = "releaseTime",
+        default,
+        deserialize_with = "deserialize_time",
+        serialize_with = "serialize_time",
+        skip_serializing_if = "Option::is_none"
+    )]
+    release_time: Option<DateTime<FixedOffset>>,
+    // When the JSON itself was last changed
+    #[serde(
+        default,
+        deserialize_with = "deserialize_time",
+        serialize_with = "serialize_time",
+        skip_serializing_if = "Option::is_none"
+    )]
+    time: Option<DateTime<FixedOffset>>,
+}
+
+// A malformed timestamp only loses the date, it must not make the version unreadable
+fn deserialize_time<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime<FixedOffset>>, D::Error> {
+    let value: Option<String> = Option::deserialize(deserializer)?;
+
+    Ok(value.and_then(|value| DateTime::parse_from_rfc3339(&value).ok()))
+}
+
+fn serialize_time<S: serde::Serializer>(time: &Option<DateTime<FixedOffset>>, serializer: S) -> Result<S::Ok, S::Error> {
+    match time {
+        Some(time) => serializer.serialize_str(&time.to_rfc3339()),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct JavaVersion {
+    // Mojang runtime name such as java-runtime-gamma
+    #[serde(skip_serializing_if = "Option::is_none")]
+    component: Option<String>,
+    #[serde(rename = "majorVersion", skip_serializing_if = "Option::is_none")]
+    major_version: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AssetIndex {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha1: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    #[serde(rename = "totalSize", skip_serializing_if = "Option::is_none")]
+    total_size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Logging {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client: Option<LoggingConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LoggingConfig {
+    // JVM flag template with ${path} standing for the downloaded file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    argument: Option<String>,
+    file: LoggingFile,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LoggingFile {
+    id: String,
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha1: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Arguments {
+    #[serde(default, deserialize_with = "deserialize_arguments")]
+    game: Vec<GameArgument>,
+    #[serde(default, deserialize_with = "deserialize_arguments", skip_serializing_if = "Vec::is_empty")]
+    jvm: Vec<GameArgument>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum GameArgument {
+    String(String),
+    Object(HashMap<String, Value>),
+}
+
+// Some third-party JSONs put bare numbers or booleans in the argument lists;
+// those become strings and anything else unusable is dropped instead of failing the whole file
+fn deserialize_arguments<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<GameArgument>, D::Error> {
+    let values: Vec<Value> = Vec::deserialize(deserializer)?;
+
+    Ok(values
+        .into_iter()
+        .filter_map(|value| match value {
+            Value::String(s) => Some(GameArgument::String(s)),
+            Value::Number(n) => Some(GameArgument::String(n.to_string())),
+            Value::Bool(b) => Some(GameArgument::String(b.to_string())),
+            Value::Object(object) => Some(GameArgument::Object(object.into_iter().collect())),
+            value => {
+                log::warn!("ignoring invalid argument entry {}", value);
+                None
+            }
+        })
+        .collect())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Library {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rules: Option<Vec<Rule>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    natives: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Rule {
+    action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    os: Option<Os>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    features: Option<HashMap<String, bool>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Os {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    arch: Option<String>,
+    // Regex matched against the OS version, mostly seen on old macOS natives
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+}
+
+#[derive(Default)]
+struct LaunchOptions {
+    // Unset means system java, falling back to a bundled java/ folder; a JDK home works too
+    java_path: Option<String>,
+    // Start the game with javaw on Windows so it opens no console window of its own
+    javaw: bool,
+    memory: Option<u32>,
+    // Defaults to memory when unset
+    min_memory: Option<u32>,
+    use_system_memory: bool,
+    // Size the heap from the installed RAM instead of memory, higher for modded instances
+    auto_memory: bool,
+    // Treat the instance as modded (Some(true)) or vanilla for auto_memory instead of detecting it
+    modded: Option<bool>,
+    use_argfile: bool,
+    isolate: bool,
+    quick_play: Option<QuickPlay>,
+    quick_play_path: Option<PathBuf>,
+    natives_dir: Option<PathBuf>,
+    // Use natives_dir as prepared, e.g. shared read-only: launches extract nothing into it and never prune it
+    skip_natives_extraction: bool,
+    access_token: Option<String>,
+    // msa when an access token is set, legacy for offline play
+    account_type: Option<AccountType>,
+    demo: bool,
+    // Play the demo instead of failing when the account does not own the game
+    demo_if_unowned: bool,
+    extra_classpath: Vec<PathBuf>,
+    // Extra entries go before the version's own so they can shadow its classes
+    prepend_extra_classpath: bool,
+    // Closes the console with the game unless it crashed
+    keep_open_on_crash: bool,
+    // Title of the console window on Windows and the Dock name on macOS; the game window
+    // always titles itself and cannot be renamed from outside
+    window_title: Option<String>,
+    // Java locale such as zh_CN as -Duser.language/-Duser.country; it changes number and date
+    // formatting and what mods see as Locale.getDefault(), but never the game's UI language
+    locale: Option<String>,
+    // Also write locale as lang in options.txt, the only thing the game itself reads its language
+    // from; it is read at startup and the in-game language menu overwrites it
+    game_language: bool,
+    // Listen for a debugger with JDWP on this local port
+    debug_port: Option<u16>,
+    // Hold the game at startup until the debugger attaches
+    debug_suspend: bool,
+    // Open an unauthenticated JMX connector on this local port for VisualVM and JConsole
+    jmx_port: Option<u16>,
+    // Auth and session servers to use instead of Mojang's, for self-hosted or air-gapped setups
+    api_endpoints: ApiEndpoints,
+    // Download and use the Mojang runtime named by javaVersion.component
+    auto_java: bool,
+    // Look for mainClass in the classpath jars before launching; slow on big modpacks
+    check_main_class: bool,
+    // Run this class instead of the version's mainClass, e.g. net.minecraft.data.Main for datagen
+    main_class: Option<String>,
+    // Delete natives-* folders in the version dir other than the one this launch uses
+    prune_natives: bool,
+    // Replace (Some) or drop (None) default JVM flags by key such as MaxGCPauseMillis; later entries win
+    jvm_flags: Vec<(String, Option<String>)>,
+    // Offline UUID to launch with instead of the one derived from the player name
+    uuid: Option<String>,
+    // Where -XX:HeapDumpPath points, <game dir>/logs when unset
+    heap_dump_path: Option<PathBuf>,
+    // Leave out -XX:HeapDumpPath altogether
+    no_heap_dump: bool,
+    // Leave out the RMI/JNDI codebase and log4j lookup flags, for testing pre-Log4Shell behavior
+    no_log4j_mitigation: bool,
+    // Follow logs/latest.log instead of showing the raw console, then report the exit code
+    tail: bool,
+    // Start the game in its own session without a console and return at once; it outlives WMML
+    detach: bool,
+    // Ctrl-C while WMML waits for the game (with --tail, --events, --timeout or --keep-open-on-crash)
+    on_interrupt: InterruptPolicy,
+    // Stop the game once it has run this long, e.g. for unattended smoke tests of a modpack
+    timeout: Option<Duration>,
+    // Launch even if the game dir lock says another instance is running
+    force: bool,
+    // Process working directory, the game dir when unset; some mods resolve ./config from it
+    working_dir: Option<PathBuf>,
+    on_event: Option<EventHandler>,
+}
+
+impl LaunchOptions {
+    fn builder() -> LaunchOptionsBuilder {
+        LaunchOptionsBuilder::default()
+    }
+
+    fn java_path(&self, required_major: Option<u32>) -> String {
+        self.java_path.clone().unwrap_or_else(|| java::default_java(required_major))
+    }
+
+    fn emit(&self, event: LaunchEvent) {
+        if let Some(on_event) = &self.on_event {
+            on_event(&event);
+        }
+    }
+}
+
+#[derive(Default)]
+struct LaunchOptionsBuilder {
+    options: LaunchOptions,
+}
+
+impl LaunchOptionsBuilder {
+    fn java_path(mut self, java_path: impl Into<String>) -> Self {
+        self.options.java_path = Some(java_path.into());
+        self
+    }
+
+    fn memory(mut self, memory: u32) -> Self {
+        self.options.memory = Some(memory);
+        self
+    }
+
+    fn min_memory(mut self, min_memory: u32) -> Self {
+        self.options.min_memory = Some(min_memory);
+        self
+    }
+
+    fn use_system_memory(mut self, use_system_memory: bool) -> Self {
+        self.options.use_system_memory = use_system_memory;
+        self
+    }
+
+    fn auto_memory(mut self, auto_memory: bool) -> Self {
+        self.options.auto_memory = auto_memory;
+        self
+    }
+
+    fn modded(mut self, modded: bool) -> Self {
+        self.options.modded = Some(modded);
+        self
+    }
+
+    fn use_argfile(mut self, use_argfile: bool) -> Self {
+        self.options.use_argfile = use_argfile;
+        self
+    }
+
+    fn isolate(mut self, isolate: bool) -> Self {
+        self.options.isolate = isolate;
+        self
+    }
+
+    fn quick_play(mut self, quick_play: QuickPlay) -> Self {
+        self.options.quick_play = Some(quick_play);
+        self
+    }
+
+    fn quick_play_path(mut self, quick_play_path: impl Into<PathBuf>) -> Self {
+        self.options.quick_play_path = Some(quick_play_path.into());
+        self
+    }
+
+    fn natives_dir(mut self, natives_dir: impl Into<PathBuf>) -> Self {
+        self.options.natives_dir = Some(natives_dir.into());
+        self
+    }
+
+    fn skip_natives_extraction(mut self, skip_natives_extraction: bool) -> Self {
+        self.options.skip_natives_extraction = skip_natives_extraction;
+        self
+    }
+
+    fn account_type(mut self, account_type: AccountType) -> Self {
+        self.options.account_type = Some(account_type);
+        self
+    }
+
+    fn access_token(mut self, access_token: impl Into<String>) -> Self {
+        self.options.access_token = Some(access_token.into());
+        self
+    }
+
+    fn demo(mut self, demo: bool) -> Self {
+        self.options.demo = demo;
+        self
+    }
+
+    fn demo_if_unowned(mut self, demo_if_unowned: bool) -> Self {
+        self.options.demo_if_unowned = demo_if_unowned;
+        self
+    }
+
+    fn extra_classpath(mut self, entry: impl Into<PathBuf>) -> Self {
+        self.options.extra_classpath.push(entry.into());
+        self
+    }
+
+    fn prepend_extra_classpath(mut self, prepend: bool) -> Self {
+        self.options.prepend_extra_classpath = prepend;
+        self
+    }
+
+    fn javaw(mut self, javaw: bool) -> Self {
+        self.options.javaw = javaw;
+        self
+    }
+
+    fn keep_open_on_crash(mut self, keep_open_on_crash: bool) -> Self {
+        self.options.keep_open_on_crash = keep_open_on_crash;
+        self
+    }
+
+    fn window_title(mut self, window_title: impl Into<String>) -> Self {
+        self.options.window_title = Some(window_title.into());
+        self
+    }
+
+    fn locale(mut self, locale: impl Into<String>) -> Self {
+        self.options.locale = Some(locale.into());
+        self
+    }
+
+    fn game_language(mut self, game_language: bool) -> Self {
+        self.options.game_language = game_language;
+        self
+    }
+
+    fn debug_port(mut self, debug_port: u16) -> Self {
+        self.options.debug_port = Some(debug_port);
+        self
+    }
+
+    fn debug_suspend(mut self, debug_suspend: bool) -> Self {
+        self.options.debug_suspend = debug_suspend;
+        self
+    }
+
+    fn jmx_port(mut self, jmx_port: u16) -> Self {
+        self.options.jmx_port = Some(jmx_port);
+        self
+    }
+
+    fn api_host(mut self, service: ApiService, host: impl Into<String>) -> Self {
+        self.options.api_endpoints.set(service, host.into());
+        self
+    }
+
+    fn auto_java(mut self, auto_java: bool) -> Self {
+        self.options.auto_java = auto_java;
+        self
+    }
+
+    fn check_main_class(mut self, check_main_class: bool) -> Self {
+        self.options.check_main_class = check_main_class;
+        self
+    }
+
+    fn main_class(mut self, main_class: impl Into<String>) -> Self {
+        self.options.main_class = Some(main_class.into());
+        self
+    }
+
+    fn prune_natives(mut self, prune_natives: bool) -> Self {
+        self.options.prune_natives = prune_natives;
+        self
+    }
+
+    // The key comes from the flag itself, so -XX:MaxGCPauseMillis=100 replaces -XX:MaxGCPauseMillis=50
+    fn jvm_flag(mut self, flag: impl Into<String>) -> Self {
+        let flag = flag.into();
+        self.options.jvm_flags.push((jvm_flag_key(&flag).to_string(), Some(flag)));
+        self
+    }
+
+    fn remove_jvm_flag(mut self, key: &str) -> Self {
+        self.options.jvm_flags.push((jvm_flag_key(key).to_string(), None));
+        self
+    }
+
+    fn uuid(mut self, uuid: impl Into<String>) -> Self {
+        self.options.uuid = Some(uuid.into());
+        self
+    }
+
+    fn heap_dump_path(mut self, heap_dump_path: impl Into<PathBuf>) -> Self {
+        self.options.heap_dump_path = Some(heap_dump_path.into());
+        self
+    }
+
+    fn no_heap_dump(mut self, no_heap_dump: bool) -> Self {
+        self.options.no_heap_dump = no_heap_dump;
+        self
+    }
+
+    fn no_log4j_mitigation(mut self, no_log4j_mitigation: bool) -> Self {
+        self.options.no_log4j_mitigation = no_log4j_mitigation;
+        self
+    }
+
+    fn tail(mut self, tail: bool) -> Self {
+        self.options.tail = tail;
+        self
+    }
+
+    fn detach(mut self, detach: bool) -> Self {
+        self.options.detach = detach;
+        self
+    }
+
+    fn on_interrupt(mut self, on_interrupt: InterruptPolicy) -> Self {
+        self.options.on_interrupt = on_interrupt;
+        self
+    }
+
+    fn timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout = Some(timeout);
+        self
+    }
+
+    fn force(mut self, force: bool) -> Self {
+        self.options.force = force;
+        self
+    }
+
+    fn working_dir(mut self, working_dir: impl Into<PathBuf>) -> Self {
+        self.options.working_dir = Some(working_dir.into());
+        self
+    }
+
+    fn on_event(mut self, on_event: impl Fn(&LaunchEvent) + 'static) -> Self {
+        self.options.on_event = Some(Box::new(on_event));
+        self
+    }
+
+    fn build(self) -> LaunchOptions {
+        self.options
+    }
+}
+
+enum QuickPlay {
+    Singleplayer(String),
+    Multiplayer(String),
+    Realms(String),
+}
+
+impl QuickPlay {
+    fn feature(&self) -> &'static str {
+        match self {
+            QuickPlay::Singleplayer(_) => "is_quick_play_singleplayer",
+            QuickPlay::Multiplayer(_) => "is_quick_play_multiplayer",
+            QuickPlay::Realms(_) => "is_quick_play_realms",
+        }
+    }
+
+    fn target(&self) -> &str {
+        match self {
+            QuickPlay::Singleplayer(target)
+            | QuickPlay::Multiplayer(target)
+            | QuickPlay::Realms(target) => target,
+        }
+    }
+}
+
+// The services authlib reaches through minecraft.api.<service>.host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ApiService {
+    Auth,
+    Account,
+    Session,
+    Services,
+}
+
+impl ApiService {
+    fn parse(value: &str) -> Option<ApiService> {
+        match value {
+            "auth" => Some(ApiService::Auth),
+            "account" => Some(ApiService::Account),
+            "session" => Some(ApiService::Session),
+            "services" => Some(ApiService::Services),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ApiService::Auth => "auth",
+            ApiService::Account => "account",
+            ApiService::Session => "session",
+            ApiService::Services => "services",
+        }
+    }
+}
+
+// Base URLs such as https://sessionserver.example.com; unset services stay on Mojang's hosts
+#[derive(Debug, Default)]
+struct ApiEndpoints {
+    hosts: Vec<(ApiService, String)>,
+}
+
+impl ApiEndpoints {
+    // Later hosts for the same service win
+    fn set(&mut self, service: ApiService, host: String) {
+        self.hosts.retain(|(existing, _)| *existing != service);
+        self.hosts.push((service, host));
+    }
+
+    fn properties(&self) -> Vec<String> {
+        self.hosts
+            .iter()
+            .map(|(service, host)| format!("-Dminecraft.api.{}.host={}", service.name(), host))
+            .collect()
+    }
+}
+
+// "session=https://sessionserver.example.com"; authlib appends its paths to the host
+fn parse_api_host(value: &str) -> Result<(ApiService, String), String> {
+    let invalid = || format!("Invalid API host: {}", value);
+    let (service, host) = value.split_once('=').ok_or_else(invalid)?;
+    let service = ApiService::parse(service.trim()).ok_or_else(invalid)?;
+    let host = host.trim().trim_end_matches('/');
+
+    if !(host.starts_with("http://") || host.starts_with("https://")) {
+        return Err(invalid());
+    }
+
+    Ok((service, host.to_string()))
+}
+
+// Passed as ${user_type}; the game and servers treat sessions differently per type
+#[derive(Debug, Clone, Copy)]
+enum AccountType {
+    Msa,
+    Mojang,
+    Legacy,
+}
+
+impl AccountType {
+    fn parse(value: &str) -> Option<AccountType> {
+        match value {
+            "msa" => Some(AccountType::Msa),
+            "mojang" => Some(AccountType::Mojang),
+            "legacy" => Some(AccountType::Legacy),
+            _ => None,
+        }
+    }
+
+    fn user_type(self) -> &'static str {
+        match self {
+            AccountType::Msa => "msa",
+            AccountType::Mojang => "mojang",
+            AccountType::Legacy => "legacy",
+        }
+    }
+}
+
+// CreateProcess refuses command lines longer than 32767 characters
+const MAX_COMMAND_LENGTH: usize = 32000;
+const CLASSPATH_SEPARATOR: &str = if cfg!(windows) { ";" } else { ":" };
+const ARGFILE_NAME: &str = "wmml-jvm.args";
+const USER_JVM_ARGS_NAME: &str = "user_jvm_args.txt";
+// JVM arguments in argfile syntax, read from mc_path, the version dir and the game dir
+const JVM_ARGS_FILE_NAME: &str = ".jvmargs";
+const HEAP_DUMP_NAME: &str = "MojangTricksIntelDriversForPerformance_javaw.exe_minecraft.exe.heapdump";
+// The newest minimumLauncherVersion the launch logic here is known to handle
+const SUPPORTED_LAUNCHER_VERSION: u32 = 21;
+const OFFLINE_ACCESS_TOKEN: &str = "00000000000000000000000000000000";
+
+fn main() {
+    logger::init();
+
+    let mc_path = ".minecraft";
+    let mut version_name = "1.20.1".to_string();
+    let mut layout = Layout::default();
+    let mut player_name = "Player123".to_string();
+
+    let mut builder = LaunchOptions::builder()
+        .memory(4096)
+        .use_system_memory(false);
+
+    // Keeps the token out of the process list and shell history
+    if let Ok(access_token) = env::var("WMML_ACCESS_TOKEN") {
+        builder = builder.access_token(access_token);
+    }
+
+    let mut args = env::args().skip(1).peekable();
+    let command = args
+        .next_if(|arg| !arg.starts_with("--"))
+        .unwrap_or_else(|| "launch".to_string());
+
+    match command.as_str() {
+        "launch" => {
+            let options = match parse_launch_args(args, builder, &mut version_name, &mut player_name, &mut layout) {
+                Ok(builder) => builder.build(),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
+
+            if let Err(e) = launch_minecraft(mc_path, &layout, &version_name, &player_name, &options) {
+                log::error!("Failed to launch Minecraft: {}", e);
+            }
+        }
+        "mods" => {
+            let action = args.next();
+            let result = match (action.as_deref(), args.next()) {
+                (None, _) => print_mods(mc_path, &layout, &version_name),
+                (Some("list"), version) => print_mods(mc_path, &layout, version.as_deref().unwrap_or(&version_name)),
+                (Some("enable"), Some(file)) => toggle_mod(mc_path, &file, true),
+                (Some("disable"), Some(file)) => toggle_mod(mc_path, &file, false),
+                _ => {
+                    eprintln!("Usage: mods [list [version] | enable <file> | disable <file>]");
+                    return;
+                }
+            };
+
+            if let Err(e) = result {
+                log::error!("Failed to manage mods: {}", e);
+            }
+        }
+        "install-optifine" => {
+            let Some(installer) = args.next_if(|arg| !arg.starts_with("--")) else {
+                eprintln!("Usage: install-optifine <OptiFine installer jar> [--java <path>]");
+                return;
+            };
+            // The installer runs on the Java a launch would pick
+            let java_path = match parse_launch_args(args, builder, &mut version_name, &mut player_name, &mut layout) {
+                Ok(builder) => java::resolve_java(&builder.build().java_path(None), false),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
+
+            match normalize_path(mc_path)
+                .and_then(|mc_path| optifine::install_optifine(&mc_path, &layout, Path::new(&installer), &java_path))
+            {
+                Ok(version_id) => println!("Installed {}", version_id),
+                Err(e) => log::error!("Failed to install OptiFine: {}", e),
+            }
+        }
+        #[cfg(feature = "async")]
+        "install-optifine-async" => {
+            let Some(installer) = args.next_if(|arg| !arg.starts_with("--")) else {
+                eprintln!("Usage: install-optifine-async <OptiFine installer jar> [--java <path>]");
+                return;
+            };
+            // The installer runs on the Java a launch would pick
+            let java_path = match parse_launch_args(args, builder, &mut version_name, &mut player_name, &mut layout) {
+                Ok(builder) => java::resolve_java(&builder.build().java_path(None), false),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
+
+            let installed = normalize_path(mc_path).and_then(|mc_path| {
+                block_on(download_async::install_optifine(mc_path, layout.clone(), PathBuf::from(installer), java_path))?
+            });
+            match installed {
+                Ok(version_id) => println!("Installed {}", version_id),
+                Err(e) => log::error!("Failed to install OptiFine: {}", e),
+            }
+        }
+        #[cfg(feature = "async")]
+        "install-async" => {
+            let Some(version_id) = args.next_if(|arg| !arg.starts_with("--")) else {
+                eprintln!("Usage: install-async <version> [--platform <os[-arch]>] [--server-jar] [--jobs <n>]");
+                return;
+            };
+            let (platform, server_jar, jobs) = match parse_install_args(args) {
+                Ok(install_args) => install_args,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
+
+            let installed = normalize_path(mc_path).map_err(WmmlError::from).and_then(|mc_path| {
+                let version_id = manifest::resolve_version_alias(&mc_path, &version_id)?;
+                block_on(download_async::install_version(mc_path, layout.clone(), version_id, platform, server_jar, true, jobs))?
+            });
+            if let Err(e) = installed {
+                log::error!("Failed to install {}: {}", version_id, e);
+                process::exit(1);
+            }
+        }
+        "install" => {
+            let version_ids: Vec<String> = iter::from_fn(|| args.next_if(|arg| !arg.starts_with("--"))).collect();
+            if version_ids.is_empty() {
+                eprintln!("Usage: install <version>... [--platform <os[-arch]>] [--server-jar] [--jobs <n>]");
+                return;
+            }
+            let (platform, server_jar, jobs) = match parse_install_args(args) {
+                Ok(install_args) => install_args,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
+
+            let install = |version_id: &str| -> Result<(), WmmlError> {
+                let mc_path = normalize_path(mc_path)?;
+                let version_id = manifest::resolve_version_alias(&mc_path, version_id)?;
+                install::install_version(&mc_path, &layout, &version_id, &platform, server_jar, true, jobs)
+            };
+
+            // Versions install side by side; shared libraries and assets are replaced atomically
+            let results: Vec<Result<(), WmmlError>> = thread::scope(|scope| {
+                let installs: Vec<_> = version_ids
+                    .iter()
+                    .map(|version_id| scope.spawn(|| install(version_id)))
+                    .collect();
+
+                installs
+                    .into_iter()
+                    .map(|handle| handle.join().unwrap_or_else(|_| Err(io::Error::other("安装线程异常退出").into())))
+                    .collect()
+            });
+
+            let mut failed = false;
+            for (version_id, result) in version_ids.iter().zip(results) {
+                if let Err(e) = result {
+                    log::error!("Failed to install {}: {}", version_id, e);
+                    failed = true;
+                }
+            }
+            if failed {
+                process::exit(1);
+            }
+        }
+        "install-mrpack" => {
+            let Some(pack) = args.next() else {
+                eprintln!("Usage: install-mrpack <pack.mrpack> [--isolate] [--jobs <n>]");
+                return;
+            };
+            let isolate = args.next_if(|arg| arg == "--isolate").is_some();
+            let jobs = match parse_jobs_arg(&mut args) {
+                Ok(jobs) => jobs,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
+
+            let result = normalize_path(mc_path)
+                .map_err(WmmlError::from)
+                .and_then(|mc_path| mrpack::install_mrpack(&mc_path, &layout, Path::new(&pack), isolate, jobs));
+            match result {
+                Ok(installed) => println!(
+                    "Installed {} ({} files), launch it with --version {}{}",
+                    installed.name,
+                    installed.files,
+                    installed.version_id,
+                    if isolate { " --isolate" } else { "" }
+                ),
+                Err(e) => {
+                    log::error!("Failed to install modpack: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        "verify" => {
+            let version_id = args.next_if(|arg| !arg.starts_with("--")).unwrap_or(version_name);
+            let jobs = match parse_jobs_arg(&mut args) {
+                Ok(jobs) => jobs,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
+
+            match verify_install(mc_path, &layout, &version_id, jobs) {
+                Ok(true) => {}
+                Ok(false) => process::exit(1),
+                Err(e) => {
+                    log::error!("Failed to verify version: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        "repair" => {
+            let (Some("--natives"), version) = (args.next().as_deref(), args.next()) else {
+                eprintln!("Usage: repair --natives [version]");
+                return;
+            };
+            let version_id = version.unwrap_or(version_name);
+
+            match normalize_path(mc_path).and_then(|mc_path| repair_natives(&mc_path, &layout, &version_id)) {
+                Ok(extracted) => println!("Extracted {} native files for {}", extracted, version_id),
+                Err(e) => {
+                    log::error!("Failed to repair natives: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        "libraries" => {
+            let version_id = args.next_if(|arg| !arg.starts_with("--")).unwrap_or(version_name);
+            let platform = match parse_platform_arg(&mut args) {
+                Ok(platform) => platform,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
+
+            if let Err(e) = print_libraries(mc_path, &layout, &version_id, &platform) {
+                log::error!("Failed to resolve libraries: {}", e);
+            }
+        }
+        "instance-java" => {
+            let (Some(version_id), java) = (args.next(), args.next()) else {
+                eprintln!("Usage: instance-java <version> [java path | --unset]");
+                return;
+            };
+
+            if let Err(e) = pin_instance_java(mc_path, &layout, &version_id, java.as_deref()) {
+                log::error!("Failed to update instance config: {}", e);
+            }
+        }
+        "versions" => {
+            if let Err(e) = print_versions(mc_path, &layout) {
+                log::error!("Failed to list versions: {}", e);
+            }
+        }
+        "broken-versions" => {
+            let options = match parse_launch_args(args, builder, &mut version_name, &mut player_name, &mut layout) {
+                Ok(builder) => builder.build(),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
+
+            match print_broken_versions(mc_path, &layout, &options) {
+                Ok(true) => {}
+                Ok(false) => process::exit(1),
+                Err(e) => {
+                    log::error!("Failed to check versions: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        "lint" => {
+            let version_id = args.next().unwrap_or(version_name);
+
+            match lint_version(mc_path, &layout, &version_id) {
+                Ok(true) => {}
+                Ok(false) => process::exit(1),
+                Err(e) => {
+                    log::error!("Failed to lint version: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        "show-version" => {
+            let Some(version_id) = args.next() else {
+                eprintln!("Usage: show-version <version>");
+                return;
+            };
+
+            if let Err(e) = print_version(mc_path, &layout, &version_id) {
+                log::error!("Failed to resolve version: {}", e);
+            }
+        }
+        "doctor" => {
+            version_name = args.next_if(|arg| !arg.starts_with("--")).unwrap_or_else(|| doctor::DOCTOR_VERSION.to_string());
+            let options = match parse_launch_args(args, builder, &mut version_name, &mut player_name, &mut layout) {
+                Ok(builder) => builder.build(),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
+
+            match doctor::run_doctor(mc_path, &layout, &version_name, &player_name, &options) {
+                Ok(true) => println!("Doctor: PASS"),
+                Ok(false) => {
+                    println!("Doctor: FAIL");
+                    process::exit(1);
+                }
+                Err(e) => {
+                    println!("Doctor: FAIL ({})", e);
+                    process::exit(1);
+                }
+            }
+        }
+        "print-command" => {
+            // The command carries the access token, so it is only shown when asked for
+            let show_token = args.next_if(|arg| arg == "--show-token").is_some();
+            let options = match parse_launch_args(args, builder, &mut version_name, &mut player_name, &mut layout) {
+                Ok(builder) => builder.build(),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
+
+            match prepare_launch(mc_path, &layout, &version_name, &player_name, &options) {
+                Ok(prepared) if show_token => println!("{}", launch_command_string(&prepared)),
+                Ok(prepared) => println!("{}", redact_command(&launch_command_string(&prepared), &options)),
+                Err(e) => log::error!("Failed to build launch command: {}", e),
+            }
+        }
+        "jvm-args" => {
+            if let Some(version) = args.next_if(|arg| !arg.starts_with("--")) {
+                version_name = version;
+            }
+            let options = match parse_launch_args(args, builder, &mut version_name, &mut player_name, &mut layout) {
+                Ok(builder) => builder.build(),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
+
+            match effective_jvm_args(mc_path, &layout, &version_name, &player_name, &options) {
+                Ok(jvm_args) => {
+                    for arg in jvm_args {
+                        println!("{}", arg);
+                    }
+                }
+                Err(e) => log::error!("Failed to build JVM arguments: {}", e),
+            }
+        }
+        "print-classpath" => {
+            let version_id = args.next_if(|arg| !arg.starts_with("--")).unwrap_or(version_name);
+            let platform = match parse_platform_arg(&mut args) {
+                Ok(platform) => platform,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
+
+            if let Err(e) = print_classpath(mc_path, &layout, &version_id, &platform) {
+                log::error!("Failed to resolve classpath: {}", e);
+            }
+        }
+        "natives-dir" => {
+            let version_id = args.next_if(|arg| !arg.starts_with("--")).unwrap_or(version_name);
+            let platform = match parse_platform_arg(&mut args) {
+                Ok(platform) => platform,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
+
+            // For capture and overlay tools that need to find the game's native libraries
+            match normalize_path(mc_path) {
+                Ok(mc_path) => println!("{}", layout::natives_dir_for(&mc_path, &layout, &version_id, &platform).display()),
+                Err(e) => log::error!("Failed to resolve natives dir: {}", e),
+            }
+        }
+        "export-profiles" => {
+            match normalize_path(mc_path).and_then(|mc_path| launcher_profiles::export_launcher_profiles(&mc_path, &layout)) {
+                Ok(added) => println!("Added {} profiles to launcher_profiles.json", added),
+                Err(e) => log::error!("Failed to export launcher profiles: {}", e),
+            }
+        }
+        "migrate-player" => {
+            let (Some(world), Some(old), Some(new)) = (args.next(), args.next(), args.next()) else {
+                eprintln!("Usage: migrate-player <world> <offline uuid|player name> <online uuid>");
+                return;
+            };
+
+            if let Err(e) = migrate_player(mc_path, &world, &old, &new) {
+                log::error!("Failed to migrate player data: {}", e);
+                process::exit(1);
+            }
+        }
+        "export-manifest" => {
+            let Some(output) = args.next() else {
+                eprintln!("Usage: export-manifest <output json> [game dir]");
+                return;
+            };
+            let game_dir = args.next().unwrap_or_else(|| mc_path.to_string());
+
+            if let Err(e) = export_instance_manifest(Path::new(&game_dir), Path::new(&output)) {
+                log::error!("Failed to export manifest: {}", e);
+            }
+        }
+        "verify-manifest" => {
+            let Some(input) = args.next() else {
+                eprintln!("Usage: verify-manifest <manifest json> [game dir]");
+                return;
+            };
+            let game_dir = args.next().unwrap_or_else(|| mc_path.to_string());
+
+            match verify_instance_manifest(Path::new(&game_dir), Path::new(&input)) {
+                Ok(true) => {}
+                Ok(false) => process::exit(1),
+                Err(e) => {
+                    log::error!("Failed to verify manifest: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        "manifest" => {
+            if let Err(e) = print_manifest(mc_path) {
+                log::error!("Failed to fetch version manifest: {}", e);
+            }
+        }
+        "profile" => {
+            if let Err(e) = print_profile(args.next().as_deref()) {
+                log::error!("Failed to fetch profile: {}", e);
+            }
+        }
+        "servers" => {
+            let game_dir = args.next().unwrap_or_else(|| mc_path.to_string());
+
+            match servers::list_servers(Path::new(&game_dir)) {
+                Ok(servers) if servers.is_empty() => println!("No servers saved in {}", game_dir),
+                Ok(servers) => {
+                    for server in servers {
+                        println!("{} - {}", server.address, server.name);
+                    }
+                }
+                Err(e) => {
+                    log::error!("Failed to read server list: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        "upload-skin" => {
+            let Some(png_path) = args.next_if(|arg| !arg.starts_with("--")) else {
+                eprintln!("Usage: upload-skin <skin png> [--slim] [access token]");
+                return;
+            };
+            let variant = match args.next_if(|arg| arg == "--slim") {
+                Some(_) => profile::SkinVariant::Slim,
+                None => profile::SkinVariant::Classic,
+            };
+            let Some(access_token) = args.next().or_else(|| env::var("WMML_ACCESS_TOKEN").ok()) else {
+                eprintln!("Offline accounts have no skin to upload, log in first");
+                process::exit(1);
+            };
+
+            match profile::upload_skin(&access_token, Path::new(&png_path), variant) {
+                Ok(()) => println!("Uploaded {} as the {} skin", png_path, variant.as_str()),
+                Err(e) => {
+                    log::error!("Failed to upload skin: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        "uuid" => {
+            let Some(name) = args.next() else {
+                eprintln!("Usage: uuid <player name>");
+                return;
+            };
+
+            match normalize_path(mc_path).and_then(|mc_path| profile::lookup_uuid(&mc_path, &name)) {
+                Ok(Some(uuid)) => println!("{}", uuid),
+                Ok(None) => {
+                    eprintln!("No premium account is named {}", name);
+                    process::exit(1);
+                }
+                Err(e) => {
+                    log::error!("Failed to look up UUID: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        other => eprintln!("Unknown command: {}", other),
+    }
+}
+
+// "--platform osx-arm64" builds for another machine; without it the host is used
+fn parse_platform_arg(args: &mut impl Iterator<Item = String>) -> Result<TargetPlatform, String> {
+    match (args.next(), args.next()) {
+        (None, _) => Ok(TargetPlatform::host().clone()),
+        (Some(arg), Some(value)) if arg == "--platform" => {
+            TargetPlatform::parse(&value).ok_or_else(|| format!("Unknown platform: {}", value))
+        }
+        (Some(arg), _) => Err(format!("Unknown argument: {}", arg)),
+    }
+}
+
+// Drives the async API on a current-thread runtime, the simplest one an embedder would use
+#[cfg(feature = "async")]
+fn block_on<F: std::future::Future>(future: F) -> io::Result<F::Output> {
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+
+    Ok(runtime.block_on(future))
+}
+
+fn parse_install_args(mut args: impl Iterator<Item = String>) -> Result<(TargetPlatform, bool, usize), String> {
+    let mut platform = TargetPlatform::host().clone();
+    let mut server_jar = false;
+    let mut jobs = install::default_jobs();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--platform" => {
+                let value = args.next().ok_or_else(|| format!("Missing value for {}", arg))?;
+                platform = TargetPlatform::parse(&value).ok_or_else(|| format!("Unknown platform: {}", value))?;
+            }
+            "--server-jar" => server_jar = true,
+            "--jobs" => jobs = parse_jobs(args.next())?,
+            other => return Err(format!("Unknown argument: {}", other)),
+        }
+    }
+
+    Ok((platform, server_jar, jobs))
+}
+
+fn parse_jobs_arg(args: &mut impl Iterator<Item = String>) -> Result<usize, String> {
+    match args.next() {
+        None => Ok(install::default_jobs()),
+        Some(arg) if arg == "--jobs" => parse_jobs(args.next()),
+        Some(arg) => Err(format!("Unknown argument: {}", arg)),
+    }
+}
+
+fn parse_jobs(value: Option<String>) -> Result<usize, String> {
+    let value = value.ok_or_else(|| "Missing value for --jobs".to_string())?;
+
+    value.parse().ok().filter(|jobs| *jobs > 0).ok_or_else(|| format!("Invalid job count: {}", value))
+}
+
+fn parse_launch_args(
+    mut args: impl Iterator<Item = String>,
+    mut builder: LaunchOptionsBuilder,
+    version_name: &mut String,
+    player_name: &mut String,
+    layout: &mut Layout,
+) -> Result<LaunchOptionsBuilder, String> {
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("Missing value for {}", arg));
+
+        builder = match arg.as_str() {
+            "--version" => {
+                *version_name = value()?;
+                builder
+            }
+            "--name" => {
+                *player_name = value()?;
+                builder
+            }
+            "--prune-natives" => builder.prune_natives(true),
+            "--jvm-flag" => builder.jvm_flag(value()?),
+            "--remove-jvm-flag" => builder.remove_jvm_flag(&value()?),
+            "--uuid" => {
+                let uuid = value()?;
+                builder.uuid(offline::parse_uuid(&uuid).ok_or_else(|| format!("Invalid UUID: {}", uuid))?)
+            }
+            "--versions-dir" => {
+                layout.versions = value()?.into();
+                builder
+            }
+            "--libraries-dir" => {
+                layout.libraries = value()?.into();
+                builder
+            }
+            "--assets-dir" => {
+                layout.assets = value()?.into();
+                builder
+            }
+            "--java" => builder.java_path(value()?),
+            "--javaw" => builder.javaw(true),
+            "--java-args-file" => builder.use_argfile(true),
+            "--isolate" => builder.isolate(true),
+            "--quick-play-singleplayer" => builder.quick_play(QuickPlay::Singleplayer(value()?)),
+            "--quick-play-multiplayer" => builder.quick_play(QuickPlay::Multiplayer(value()?)),
+            "--quick-play-realms" => builder.quick_play(QuickPlay::Realms(value()?)),
+            "--quick-play-path" => builder.quick_play_path(value()?),
+            "--natives-dir" => builder.natives_dir(value()?),
+            "--skip-natives-extraction" => builder.skip_natives_extraction(true),
+            "--demo" => builder.demo(true),
+            "--demo-if-unowned" => builder.demo_if_unowned(true),
+            "--extra-classpath" => builder.extra_classpath(value()?),
+            "--prepend-classpath" => builder.prepend_extra_classpath(true),
+            "--keep-open-on-crash" => builder.keep_open_on_crash(true),
+            "--title" => builder.window_title(value()?),
+            "--locale" => {
+                let locale = value()?;
+                let (language, country) = game_options::parse_locale(&locale).ok_or_else(|| format!("Invalid locale: {}", locale))?;
+                builder.locale(game_options::language_code(&language, country.as_deref(), false))
+            }
+            "--game-lang" => builder.game_language(true),
+            "--debug-port" => builder.debug_port(parse_port(&value()?)?),
+            "--debug-suspend" => builder.debug_suspend(true),
+            "--jmx-port" => builder.jmx_port(parse_port(&value()?)?),
+            "--api-host" => {
+                let (service, host) = parse_api_host(&value()?)?;
+                builder.api_host(service, host)
+            }
+            "--auto-java" => builder.auto_java(true),
+            "--check-main-class" => builder.check_main_class(true),
+            "--main-class" => builder.main_class(value()?),
+            "--heap-dump-path" => builder.heap_dump_path(value()?),
+            "--no-heap-dump" => builder.no_heap_dump(true),
+            "--no-log4j-mitigation" => builder.no_log4j_mitigation(true),
+            "--detach" => builder.detach(true),
+            "--on-interrupt" => {
+                let policy = value()?;
+                builder.on_interrupt(InterruptPolicy::parse(&policy).ok_or_else(|| format!("Invalid interrupt policy: {}", policy))?)
+            }
+            "--timeout" => {
+                let timeout = value()?;
+                let seconds = timeout.parse().ok().filter(|seconds| *seconds > 0).ok_or_else(|| format!("Invalid timeout: {}", timeout))?;
+                builder.timeout(Duration::from_secs(seconds))
+            }
+            "--tail" => builder.tail(true),
+            "--force" => builder.force(true),
+            "--working-dir" => builder.working_dir(value()?),
+            "--access-token" => builder.access_token(value()?),
+            "--user-type" => {
+                let user_type = value()?;
+                builder.account_type(AccountType::parse(&user_type).ok_or_else(|| format!("Invalid user type: {}", user_type))?)
+            }
+            "--xmx" => builder.memory(parse_memory_size(&value()?)?),
+            "--xms" => builder.min_memory(parse_memory_size(&value()?)?),
+            "--auto-memory" => builder.auto_memory(true),
+            "--modded" => builder.modded(true),
+            "--vanilla" => builder.modded(false),
+            "--events" => builder.on_event(events::print_event),
+            other => return Err(format!("Unknown argument: {}", other)),
+        };
+    }
+
+    Ok(builder)
+}
+
+fn parse_port(value: &str) -> Result<u16, String> {
+    value.parse().ok().filter(|port| *port > 0).ok_or_else(|| format!("Invalid port: {}", value))
+}
+
+// Accepts JVM style sizes (512M, 6G, 1048576K) and plain megabytes
+fn parse_memory_size(value: &str) -> Result<u32, String> {
+    let value = value.trim();
+    let (number, unit_kb) = match value.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&value[..i], 1),
+        Some((i, 'm' | 'M')) => (&value[..i], 1024),
+        Some((i, 'g' | 'G')) => (&value[..i], 1024 * 1024),
+        _ => (value, 1024),
+    };
+
+    number
+        .parse::<u64>()
+        .ok()
+        .map(|n| n * unit_kb / 1024)
+        .filter(|mb| *mb > 0)
+        .and_then(|mb| u32::try_from(mb).ok())
+        .ok_or_else(|| format!("Invalid memory size: {}", value))
+}
+
+fn print_mods(mc_path: &str, layout: &Layout, version_name: &str) -> io::Result<()> {
+    let game_dir = normalize_path(mc_path)?;
+    let mods = mods::list_mods(&game_dir)?;
+    let mut seen_ids: HashMap<&str, &str> = HashMap::new();
+
+    // Without a resolvable version there is nothing to compare the mods against
+    let instance_loader = resolve_version(&game_dir, layout, version_name)
+        .ok()
+        .map(|version_json| mods::instance_loader(&version_json));
+
+    for m in &mods {
+        println!(
+            "[{}] {} {} ({}) [{}] - {}",
+            if m.enabled { "x" } else { " " },
+            m.id.as_deref().unwrap_or("?"),
+            m.version.as_deref().unwrap_or("?"),
+            m.name.as_deref().unwrap_or(""),
+            m.loader.map(|l| l.name()).unwrap_or("?"),
+            m.file_name
+        );
+
+        // A mod for another loader is skipped or crashes the game with an unrelated error
+        if let (true, Some(instance_loader), Some(mod_loader)) = (m.enabled, instance_loader, m.loader) {
+            match instance_loader {
+                Some(loader) if !loader.can_load(mod_loader) => println!(
+                    "Warning: {} is a {} mod but {} uses {}",
+                    m.file_name, mod_loader.name(), version_name, loader.name()
+                ),
+                None => println!(
+                    "Warning: {} is a {} mod but {} has no mod loader",
+                    m.file_name, mod_loader.name(), version_name
+                ),
+                _ => {}
+            }
+        }
+
+        // Only enabled copies are loaded, so disabled duplicates are harmless
+        if let (true, Some(id)) = (m.enabled, m.id.as_deref()) {
+            if let Some(other) = seen_ids.insert(id, &m.file_name) {
+                println!("Warning: duplicate mod id {} in {} and {}", id, other, m.file_name);
+            }
+        }
+    }
+
+    println!("{} mods found", mods.len());
+
+    Ok(())
+}
+
+fn toggle_mod(mc_path: &str, mod_file: &str, enabled: bool) -> io::Result<()> {
+    let game_dir = normalize_path(mc_path)?;
+    mods::set_mod_enabled(&game_dir, mod_file, enabled)?;
+
+    println!("{} {}", if enabled { "Enabled" } else { "Disabled" }, mod_file);
+
+    Ok(())
+}
+
+// The world is a folder under saves/ or a path; an old player name stands for its offline UUID
+fn migrate_player(mc_path: &str, world: &str, old: &str, new: &str) -> io::Result<()> {
+    let old_uuid = offline::parse_uuid(old).unwrap_or_else(|| offline::offline_uuid(old));
+    let new_uuid = offline::parse_uuid(new).ok_or_else(|| io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("无效的 UUID: {}", new)))?;
+
+    let saves_world = normalize_path(mc_path)?.join("saves").join(world);
+    let world_dir = if saves_world.is_dir() { saves_world } else { PathBuf::from(world) };
+
+    let renamed = offline::migrate_player_data(&world_dir, &old_uuid, &new_uuid)?;
+    for path in &renamed {
+        println!("Renamed to {}", path.display());
+    }
+    println!("Migrated {} files from {} to {}", renamed.len(), old_uuid, new_uuid);
+
+    Ok(())
+}
+
+// The version's own JSON, not merged with what it inherits
+fn lint_version(mc_path: &str, layout: &Layout, version_id: &str) -> io::Result<bool> {
+    let version_json = read_version_json(&version_json_path(&normalize_path(mc_path)?, layout, version_id))?;
+    let warnings = versions::lint_version_json(&version_json);
+
+    for warning in &warnings {
+        println!("{}: {}", version_id, warning);
+    }
+    println!("{} warnings in {}", warnings.len(), version_id);
+
+    Ok(warnings.is_empty())
+}
+
+fn print_versions(mc_path: &str, layout: &Layout) -> io::Result<()> {
+    let versions = versions::list_versions(&normalize_path(mc_path)?, layout)?;
+
+    for version in &versions {
+        let date = |time: Option<DateTime<FixedOffset>>| {
+            time.map(|time| time.format("%Y-%m-%d").to_string()).unwrap_or_else(|| "-".to_string())
+        };
+        println!(
+            "{} ({}) released {}, updated {}",
+            version.id,
+            version.version_type.as_deref().unwrap_or("unknown"),
+            date(version.release_time),
+            date(version.time)
+        );
+    }
+
+    println!("{} versions installed", versions.len());
+
+    Ok(())
+}
+
+// Returns true when every installed version looks launchable
+fn print_broken_versions(mc_path: &str, layout: &Layout, options: &LaunchOptions) -> io::Result<bool> {
+    let broken = versions::find_broken_versions(&normalize_path(mc_path)?, layout, options)?;
+
+    for version in &broken {
+        println!("{}:", version.id);
+        for problem in &version.problems {
+            println!("  {}", problem);
+        }
+    }
+
+    if broken.is_empty() {
+        println!("All installed versions look launchable");
+    } else {
+        println!("{} broken versions", broken.len());
+    }
+
+    Ok(broken.is_empty())
+}
+
+fn print_version(mc_path: &str, layout: &Layout, version_id: &str) -> io::Result<()> {
+    let version_json = resolve_version(&normalize_path(mc_path)?, layout, version_id)?;
+    println!("{}", serde_json::to_string_pretty(&version_json)?);
+
+    Ok(())
+}
+
+fn print_classpath(mc_path: &str, layout: &Layout, version_id: &str, platform: &TargetPlatform) -> io::Result<()> {
+    let mc_path = normalize_path(mc_path)?;
+
+    let classpath = if !version_json_path(&mc_path, layout, version_id).exists() && legacy::is_legacy_layout(&mc_path) {
+        legacy::classpath(&mc_path)?
+    } else {
+        collect_classpath(&mc_path, layout, &resolve_version(&mc_path, layout, version_id)?, platform)
+    };
+
+    let mut missing = 0;
+    for entry in &classpath {
+        if entry.exists() {
+            println!("  {}", entry.display());
+        } else {
+            missing += 1;
+            println!("! {} (missing)", entry.display());
+        }
+    }
+
+    println!("{} entries, {} missing", classpath.len(), missing);
+
+    Ok(())
+}
+
+fn print_manifest(mc_path: &str) -> io::Result<()> {
+    let manifest = manifest::fetch_version_manifest(&normalize_path(mc_path)?)?;
+
+    for version in &manifest.versions {
+        println!("{} ({})", version.id, version.version_type);
+    }
+
+    println!(
+        "Latest release: {}, latest snapshot: {}",
+        manifest.latest.release, manifest.latest.snapshot
+    );
+
+    Ok(())
+}
+
+// Without a path this only shows the current pin
+fn pin_instance_java(mc_path: &str, layout: &Layout, version_id: &str, java: Option<&str>) -> io::Result<()> {
+    let mc_path = normalize_path(mc_path)?;
+    let mut config = instance::read_config(&mc_path, layout, version_id)?;
+
+    match java {
+        None => {}
+        Some("--unset") => config.java_path = None,
+        Some(java) => config.java_path = Some(java.to_string()),
+    }
+    if java.is_some() {
+        instance::write_config(&mc_path, layout, version_id, &config)?;
+    }
+
+    match &config.java_path {
+        Some(java) => println!("{} uses Java {}", version_id, java),
+        None => println!("{} uses the default Java", version_id),
+    }
+
+    Ok(())
+}
+
+fn print_libraries(mc_path: &str, layout: &Layout, version_id: &str, platform: &TargetPlatform) -> io::Result<()> {
+    let report = install::resolve_libraries(&normalize_path(mc_path)?, layout, version_id, platform)?;
+
+    for download in &report.downloadable {
+        match &download.sha1 {
+            Some(sha1) => println!("download {} from {} (sha1 {})", download.path.display(), download.url, sha1),
+            None => println!("download {} from {}", download.path.display(), download.url),
+        }
+    }
+    for path in &report.missing {
+        println!("missing  {} (no download source)", path.display());
+    }
+
+    println!(
+        "{} present, {} downloadable, {} missing without a source",
+        report.present.len(),
+        report.downloadable.len(),
+        report.missing.len()
+    );
+
+    Ok(())
+}
+
+fn verify_install(mc_path: &str, layout: &Layout, version_id: &str, jobs: usize) -> io::Result<bool> {
+    let mc_path = normalize_path(mc_path)?;
+    let report = install::verify_version(&mc_path, layout, version_id, jobs)?;
+
+    for path in &report.missing {
+        println!("missing {}", path.display());
+    }
+    for path in &report.corrupt {
+        println!("corrupt {}", path.display());
+    }
+
+    println!(
+        "Checked {} files: {} missing, {} corrupt",
+        report.checked, report.missing.len(), report.corrupt.len()
+    );
+
+    Ok(report.missing.is_empty() && report.corrupt.is_empty())
+}
+
+// Files matched by <game dir>/.mcignore are neither exported nor verified
+fn export_instance_manifest(game_dir: &Path, output: &Path) -> io::Result<()> {
+    let manifest = export::export_manifest(game_dir)?;
+    export::write_manifest(output, &manifest)?;
+
+    println!("Exported {} files to {}", manifest.files.len(), output.display());
+
+    Ok(())
+}
+
+fn verify_instance_manifest(game_dir: &Path, input: &Path) -> io::Result<bool> {
+    let manifest = export::read_manifest(input)?;
+    let report = export::verify_against_manifest(game_dir, &manifest)?;
+
+    for path in &report.missing {
+        println!("missing  {}", path);
+    }
+    for path in &report.modified {
+        println!("modified {}", path);
+    }
+
+    println!(
+        "Checked {} files: {} missing, {} modified",
+        report.checked, report.missing.len(), report.modified.len()
+    );
+
+    Ok(report.missing.is_empty() && report.modified.is_empty())
+}
+
+fn print_profile(access_token: Option<&str>) -> io::Result<()> {
+    let Some(profile) = profile::fetch_profile(access_token)? else {
+        println!("Offline account, no profile available");
+        return Ok(());
+    };
+
+    println!("{} ({})", profile.name, profile.id);
+
+    if let Some(skin) = profile.active_skin() {
+        println!("Skin: {} [{}]", skin.url, skin.variant.as_deref().unwrap_or("CLASSIC"));
+    }
+
+    if let Some(cape) = profile.active_cape() {
+        println!("Cape: {} [{}]", cape.url, cape.alias.as_deref().unwrap_or(""));
+    }
+
+    Ok(())
+}
+
+// The JVM arguments a launch would use after the whole precedence chain of merge_jvm_args,
+// with arguments.jvm substituted and never hidden behind an argfile
+fn effective_jvm_args(
+    mc_path: &str,
+    layout: &Layout,
+    version_name: &str,
+    player_name: &str,
+    options: &LaunchOptions,
+) -> Result<Vec<String>, WmmlError> {
+    Ok(prepare_launch(mc_path, layout, version_name, player_name, options)?.jvm_args)
+}
+
+// The finished java command line plus the environment it needs, before anything is spawned
+struct PreparedLaunch {
+    // java first, then every argument exactly as the JVM receives it
+    args: Vec<String>,
+    // Every JVM argument in order, even those args only reference through an argfile
+    jvm_args: Vec<String>,
+    game_dir: PathBuf,
+    working_dir: PathBuf,
+    env: Vec<(&'static str, PathBuf)>,
+    // Console window title on Windows
+    title: Option<String>,
+}
+
+impl PreparedLaunch {
+    // java itself is the child, so no argument is split or read by a shell on the way and the
+    // PID WMML sees is the JVM's
+    fn command(&self) -> Command {
+        let mut command = Command::new(&self.args[0]);
+        command.args(&self.args[1..]).current_dir(&self.working_dir);
+
+        for (key, value) in &self.env {
+            command.env(key, value);
+        }
+
+        // java runs in WMML's console, so that is the window to name
+        if let Some(title) = &self.title {
+            set_console_title(title);
+        }
+
+        command
+    }
+}
+
+// The line a shell would run, for the log and the command line length limit
+fn command_line(args: &[String]) -> String {
+    args.iter().map(|arg| shell::quote(arg)).collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(windows)]
+fn set_console_title(title: &str) {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetConsoleTitleW(title: *const u16) -> i32;
+    }
+
+    let wide_title: Vec<u16> = title.encode_utf16().chain([0]).collect();
+    // SAFETY: the title is NUL terminated and only read during the call
+    if unsafe { SetConsoleTitleW(wide_title.as_ptr()) } == 0 {
+        log::debug!("cannot set the console title: {}", io::Error::last_os_error());
+    }
+}
+
+// Terminals elsewhere name their windows themselves
+#[cfg(not(windows))]
+fn set_console_title(_title: &str) {}
+
+// A runnable one-liner for scripts, quoted for the shell of the current platform
+fn launch_command_string(prepared: &PreparedLaunch) -> String {
+    let env = prepared
+        .env
+        .iter()
+        .map(|(key, value)| shell::env_prefix(key, &value.to_string_lossy()));
+    let args = prepared.args.iter().map(|arg| shell::quote(arg));
+
+    env.chain(args).collect::<Vec<_>>().join(" ")
+}
+
+fn launch_minecraft(
+    mc_path: &str,
+    layout: &Layout,
+    version_name: &str,
+    player_name: &str,
+    options: &LaunchOptions,
+) -> Result<(), WmmlError> {
+    let prepared = prepare_launch(mc_path, layout, version_name, player_name, options)?;
+
+    // Two clients writing the same saves corrupt them
+    let lock = lock::InstanceLock::acquire(&prepared.game_dir, options.force)?;
+
+    // Execute command
+    let session = if options.detach {
+        spawn_detached(&prepared, options, lock)?;
+        None
+    } else if options.tail {
+        spawn_tailed(&prepared, options, lock)?
+    } else {
+        let wait = options.keep_open_on_crash || options.timeout.is_some();
+        spawn_game(prepared.command(), wait, &prepared.game_dir, options, lock)?
+    };
+
+    if let Some((status, outcome)) = session {
+        println!("Minecraft {}", outcome);
+
+        if let (true, SessionOutcome::Crash { report }) = (options.keep_open_on_crash, &outcome) {
+            hold_after_crash(&prepared.game_dir, status, report.as_deref())?;
+        }
+    }
+
+    Ok(())
+}
+
+const CRASH_LOG_LINES: usize = 30;
+// How often a waiting launch checks for the game's exit and Ctrl-C
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+fn hold_after_crash(game_dir: &Path, status: ExitStatus, report: Option<&Path>) -> io::Result<()> {
+    eprintln!("Minecraft exited abnormally ({})", status);
+    if let Some(report) = report {
+        eprintln!("Crash report: {}", report.display());
+    }
+
+    let log_path = game_dir.join("logs").join("latest.log");
+    if let Ok(log) = fs::read_to_string(&log_path) {
+        let lines: Vec<&str> = log.lines().collect();
+
+        eprintln!("Last lines of {}:", log_path.display());
+        for line in &lines[lines.len().saturating_sub(CRASH_LOG_LINES)..] {
+            eprintln!("{}", line);
+        }
+    }
+
+    eprintln!("Press Enter to close");
+    io::stdin().read_line(&mut String::new())?;
+
+    Ok(())
+}
+
+fn prepare_launch(
+    mc_path: &str,
+    layout: &Layout,
+    version_name: &str,
+    player_name: &str,
+    options: &LaunchOptions,
+) -> Result<PreparedLaunch, WmmlError> {
+    if let (Some(max), Some(min)) = (options.memory, options.min_memory) {
+        if min > max {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("最小内存 {}M 不能大于最大内存 {}M", min, max)).into());
+        }
+    }
+
+    // Normalize path; absolute because the game runs from its own working directory
+    let mc_path = path::absolute(normalize_path(mc_path)?)?;
+
+    // A real token must belong to an account that owns the game
+    let demo = options.demo || check_ownership(options)?;
+
+    // Clients older than 1.6 have no version JSON, only bin/minecraft.jar
+    if !version_json_path(&mc_path, layout, version_name).exists() && legacy::is_legacy_layout(&mc_path) {
+        return prepare_legacy(&mc_path, player_name, options);
+    }
+
+    // Resolve latest / latest-release / latest-snapshot to a concrete id
+    let version_name = &manifest::resolve_version_alias(&mc_path, version_name)?;
+
+    // Read version JSON file, merged with any versions it inherits from
+    options.emit(LaunchEvent::ResolvingVersion { version: version_name.clone() });
+    let mut version_json = resolve_version(&mc_path, layout, version_name)?;
+    if let Some(main_class) = &options.main_class {
+        log::info!("Running {} instead of {}", main_class, version_json.main_class);
+        version_json.main_class = main_class.clone();
+    }
+
+    if let Some(required) = version_json.minimum_launcher_version.filter(|&v| v > SUPPORTED_LAUNCHER_VERSION) {
+        log::warn!(
+            "{} requires launcher version {} but WMML supports up to {}, the game may not start correctly",
+            version_name,
+            required,
+            SUPPORTED_LAUNCHER_VERSION
+        );
+    }
+
+    // Fail early with a clear error instead of an IO error halfway through on read-only shares
+    ensure_writable(&layout.version_dir(&mc_path, version_name))?;
+
+    match install::verify_client_jar(&mc_path, layout, &version_json) {
+        Ok(true) => {}
+        Ok(false) => log::warn!(
+            "{} does not match its version JSON, run install {} to repair it",
+            client_jar_path(&mc_path, layout, &version_json).display(),
+            version_json.jar.as_deref().unwrap_or(&version_json.id)
+        ),
+        Err(e) => log::warn!("cannot verify the client jar: {}", e),
+    }
+
+    let version_dir = layout.version_dir(&mc_path, version_name);
+    let natives_dir = match &options.natives_dir {
+        Some(natives_dir) => path::absolute(natives_dir)?,
+        None => layout::natives_dir_for(&mc_path, layout, version_name, TargetPlatform::host()),
+    };
+    check_prepared_natives(&natives_dir, options)?;
+    if !options.skip_natives_extraction {
+        let extracted = extract_natives(&mc_path, layout, &version_json, TargetPlatform::host(), &natives_dir)
+            .map_err(|e| io::Error::new(e.kind(), format!("无法解压 natives 到 {}: {}", natives_dir.display(), e)))?;
+        if extracted > 0 {
+            log::info!("Extracted {} native files to {}", extracted, natives_dir.display());
+        }
+    }
+
+    // Natives folders of other layouts or platforms are never read again
+    if options.prune_natives && options.skip_natives_extraction {
+        log::warn!("not pruning natives folders, natives extraction is skipped");
+    } else if options.prune_natives {
+        match prune_natives_dirs(&version_dir, &natives_dir) {
+            Ok(0) => {}
+            Ok(removed) => println!("Removed {} stale natives folders", removed),
+            Err(e) => log::warn!("failed to prune natives folders: {}", e),
+        }
+    }
+
+    // JVM arguments from the version JSON; Forge 1.17+ puts its module path there
+    let version_jvm_args = build_version_jvm_arguments(
+        &layout.libraries_dir(&mc_path),
+        &natives_dir,
+        version_name,
+        &version_json,
+    );
+
+    // Build libraries path, leaving out what already sits on the module path
+    let libraries = build_libraries_path(
+        &mc_path,
+        layout,
+        version_name,
+        &version_json,
+        &module_path_entries(&version_jvm_args),
+        options,
+    )?;
+
+    // Catches modpacks whose mainClass ships in none of their jars before the JVM does
+    if options.check_main_class {
+        let mut classpath = launch_classpath(&mc_path, layout, version_name, &version_json);
+        classpath.extend(options.extra_classpath.iter().cloned());
+        check_main_class(&classpath, &version_json.main_class);
+    }
+
+    // Saves, config and mods live in a private game dir when isolated
+    let game_dir = if options.isolate {
+        instance::prepare_isolated_game_dir(&mc_path, layout, version_name)?
+    } else {
+        mc_path.clone()
+    };
+    ensure_writable(&game_dir)?;
+
+    // Pre-1.6 versions read their assets from the game dir, which may not be the one install filled
+    if let Some(index) = &version_json.asset_index {
+        let assets_dir = layout.assets_dir(&mc_path);
+        let index_path = assets_dir.join("indexes").join(format!("{}.json", index.id));
+        if index_path.exists() {
+            if let Err(e) = install::lay_out_legacy_assets(&index_path, &assets_dir, &game_dir.join("resources")) {
+                log::warn!("cannot lay out the legacy assets: {}", e);
+            }
+        }
+    }
+
+    if let (Some((language, country)), true) = (options.locale.as_deref().and_then(game_options::parse_locale), options.game_language) {
+        let code = game_options::language_code(&language, country.as_deref(), game_options::lower_case_language(version_json.release_time));
+        game_options::set_option(&game_dir, "lang", &code)?;
+    }
+
+    // Looked up here so building the arguments never waits on the network
+    if let Some(quick_play @ QuickPlay::Multiplayer(address)) = &options.quick_play {
+        if supports_feature(&version_json, quick_play.feature()) {
+            report_server_address(address);
+        }
+    }
+
+    // Build game arguments
+    options.emit(LaunchEvent::BuildingArguments);
+    let game_args = build_game_arguments_vec(
+        &layout.assets_dir(&mc_path),
+        &game_dir,
+        version_name,
+        player_name,
+        &version_json,
+        demo,
+        options,
+    );
+
+    // A Java pinned for the instance beats detection; --java on the command line still wins
+    let pinned_java = instance::read_config(&mc_path, layout, version_name)?.java_path;
+    let java_path = match (&options.java_path, pinned_java) {
+        (None, Some(pinned_java)) => {
+            log::info!("Using Java {} pinned for {}", pinned_java, version_name);
+            pinned_java
+        }
+        // Use the exact Mojang runtime the version was tested with when asked to
+        _ => select_java(&mc_path, &version_json, options),
+    };
+
+    let java_path = java::resolve_java(&java_path, false);
+    let memory = launch_memory(&version_json, &game_dir, options);
+
+    // Report the Java bitness and catch setups a 32-bit JRE cannot run
+    let java_major = check_java(&java_path, memory);
+    let java_path = java::resolve_java(&java_path, options.javaw);
+
+    // Fetch the logging config referenced by the version JSON
+    let log_config = prepare_log_config(&mc_path, layout, version_name, &version_json, options);
+
+    // Build JVM arguments
+    let user_jvm_args = read_jvm_args_files(&mc_path, Some(&version_dir), &game_dir)?;
+    let client_jar = client_jar_path(&mc_path, layout, &version_json);
+    let heap_dump = match (&options.heap_dump_path, options.no_heap_dump) {
+        (_, true) => None,
+        (Some(path), false) => Some(path::absolute(path)?),
+        (None, false) => Some(game_dir.join("logs").join(HEAP_DUMP_NAME)),
+    };
+    let paths = JvmPaths {
+        natives_dir: &natives_dir,
+        client_jar: &client_jar,
+        log_config: &log_config,
+        log_config_argument: version_json
+            .logging
+            .as_ref()
+            .and_then(|l| l.client.as_ref())
+            .and_then(|client| client.argument.as_deref()),
+        heap_dump: heap_dump.as_deref(),
+    };
+    let mut jvm_args = build_jvm_arguments(
+        &paths,
+        &libraries,
+        &version_jvm_args,
+        &user_jvm_args,
+        memory,
+        options,
+    );
+
+    // Build Java command
+    let resolved_jvm_args = jvm_args.clone();
+    let mut args = java_arguments(&java_path, &jvm_args, &version_json.main_class, &game_args);
+
+    // Only Windows limits the command line. JVM arguments move into an argfile there when it gets
+    // too long, which Java 8 cannot read
+    let too_long = cfg!(windows) && command_line(&args).len() > MAX_COMMAND_LENGTH;
+    let argfile_supported = java_major.is_some_and(|major| major >= 9);
+    if too_long && !argfile_supported && !options.use_argfile {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "启动命令超过 {} 个字符，而 Java {} 不支持参数文件，请使用 Java 9 或更高版本",
+                MAX_COMMAND_LENGTH,
+                java_major.map_or("(未知版本)".to_string(), |major| major.to_string())
+            ),
+        ).into());
+    }
+    if options.use_argfile || too_long {
+        let argfile_path = layout.version_dir(&mc_path, version_name).join(ARGFILE_NAME);
+        write_argfile(&argfile_path, &jvm_args)?;
+        jvm_args = vec![format!("@{}", argfile_path.display())];
+
+        args = java_arguments(&java_path, &jvm_args, &version_json.main_class, &game_args);
+    }
+
+    log::info!("Launching Minecraft with command: {}", redact_command(&command_line(&args), options));
+
+    let working_dir = working_dir(&game_dir, options)?;
+
+    Ok(PreparedLaunch {
+        args,
+        jvm_args: resolved_jvm_args,
+        game_dir,
+        working_dir,
+        env: Vec::new(),
+        title: options.window_title.clone(),
+    })
+}
+
+fn prepare_legacy(mc_path: &Path, player_name: &str, options: &LaunchOptions) -> Result<PreparedLaunch, WmmlError> {
+    log::info!("No version JSON found, launching the legacy bin/minecraft.jar layout");
+    options.emit(LaunchEvent::ResolvingVersion { version: "legacy".to_string() });
+
+    options.emit(LaunchEvent::BuildingArguments);
+    let java_path = java::resolve_java(&options.java_path(None), false);
+    let memory = options.memory.filter(|_| !options.use_system_memory);
+    check_java(&java_path, memory);
+    let java_path = java::resolve_java(&java_path, options.javaw);
+
+    let classpath = join_classpath(legacy::classpath(mc_path)?, options);
+    let natives_dir = match &options.natives_dir {
+        Some(natives_dir) => path::absolute(natives_dir)?,
+        None => legacy::natives_dir(mc_path),
+    };
+    check_prepared_natives(&natives_dir, options)?;
+
+    // The game finds its folder through APPDATA on Windows and user.home elsewhere
+    let os = &TargetPlatform::host().os;
+    let mut envs = Vec::new();
+    let mut jvm_args = Vec::new();
+    match legacy::home_for(&path::absolute(mc_path)?, os) {
+        Some(home) if cfg!(windows) => envs.push(("APPDATA", home)),
+        Some(home) => jvm_args.push(format!("-Duser.home={}", home.display())),
+        None => log::warn!(
+            "legacy clients only read {} in the home folder, {} may be ignored",
+            legacy::folder_path(os).display(),
+            mc_path.display()
+        ),
+    }
+
+    if let Some(memory) = memory {
+        jvm_args.push(format!("-Xmx{}M", memory));
+        jvm_args.push(format!("-Xms{}M", options.min_memory.unwrap_or(memory)));
+    }
+    jvm_args.push(format!("-Djava.library.path={}", natives_dir.display()));
+    jvm_args.push("-Dminecraft.launcher.brand=WMML".to_string());
+    jvm_args.extend(locale_properties(options));
+    jvm_args.extend(debug_arguments(options));
+    let jvm_args = merge_jvm_args(jvm_args, &read_jvm_args_files(mc_path, None, mc_path)?);
+    let mut jvm_args = apply_jvm_flag_overrides(jvm_args, &options.jvm_flags);
+    jvm_args.push("-cp".to_string());
+    jvm_args.push(classpath);
+
+    // These clients take the player name and session id as plain positional arguments
+    let session = options.access_token.as_deref().unwrap_or("-");
+    let game_args = [player_name.to_string(), session.to_string()];
+
+    // Old Java has no argfile support, but this command line is short anyway
+    let main_class = options.main_class.as_deref().unwrap_or(legacy::MAIN_CLASS);
+    let args = java_arguments(&java_path, &jvm_args, main_class, &game_args);
+    log::info!("Launching Minecraft with command: {}", redact_command(&command_line(&args), options));
+
+    Ok(PreparedLaunch {
+        args,
+        jvm_args,
+        game_dir: mc_path.to_path_buf(),
+        working_dir: working_dir(mc_path, options)?,
+        env: envs,
+        title: options.window_title.clone(),
+    })
+}
+
+fn working_dir(game_dir: &Path, options: &LaunchOptions) -> io::Result<PathBuf> {
+    match &options.working_dir {
+        Some(working_dir) => {
+            fs::create_dir_all(working_dir)?;
+            path::absolute(working_dir)
+        }
+        None => Ok(game_dir.to_path_buf()),
+    }
+}
+
+// The exit status is only collected when asked to wait or when someone listens for events
+fn spawn_game(
+    mut command: Command,
+    wait: bool,
+    game_dir: &Path,
+    options: &LaunchOptions,
+    lock: lock::InstanceLock,
+) -> io::Result<Option<(ExitStatus, SessionOutcome)>> {
+    options.emit(LaunchEvent::Spawning);
+
+    // Subscribers get told when the game closes
+    let wait = wait || options.on_event.is_some();
+    if wait {
+        interrupt::isolate_command(&mut command);
+    }
+
+    let mut watch = SessionWatch::start(options.on_interrupt, options.timeout);
+
+    // Subscribers are told when the game is ready, which means reading its output
+    let stdout = if options.on_event.is_some() { Stdio::piped() } else { Stdio::inherit() };
+    let mut child = command
+        .stdout(stdout)
+        .stderr(Stdio::inherit())
+        .spawn()?;
+
+    log::info!("Minecraft launched with PID: {}", child.id());
+    options.emit(LaunchEvent::Spawned { pid: child.id() });
+
+    if !wait {
+        lock.hand_over(child.id())?;
+        return Ok(None);
+    }
+
+    let _interrupts = interrupt::InterruptGuard::install();
+
+    // Output goes through its own thread so Ctrl-C is noticed while the game is quiet
+    let (ready_sender, ready) = mpsc::channel();
+    let forwarder = child.stdout.take().map(|stdout| thread::spawn(move || forward_output(stdout, ready_sender)));
+
+    let status = loop {
+        match ready.recv_timeout(WAIT_POLL_INTERVAL) {
+            Ok(line) => options.emit(LaunchEvent::Ready { line }),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => thread::sleep(WAIT_POLL_INTERVAL),
+        }
+
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+
+        // A detached game keeps writing into the pipe after WMML is gone, which Java ignores
+        if watch.check(&mut child)? {
+            lock.hand_over(child.id())?;
+            return Ok(None);
+        }
+    };
+
+    if let Some(forwarder) = forwarder {
+        forwarder.join().map_err(|_| io::Error::other("游戏输出转发线程异常退出"))??;
+    }
+    for line in ready.try_iter() {
+        options.emit(LaunchEvent::Ready { line });
+    }
+
+    drop(lock);
+    let outcome = watch.outcome(game_dir, status);
+    options.emit(LaunchEvent::Exited { code: status.code(), outcome: outcome.clone() });
+
+    Ok(Some((status, outcome)))
+}
+
+// The console output repeats latest.log, in log4j XML on many versions, so only stderr is kept
+// None when Ctrl-C left the game running
+fn spawn_tailed(
+    prepared: &PreparedLaunch,
+    options: &LaunchOptions,
+    lock: lock::InstanceLock,
+) -> io::Result<Option<(ExitStatus, SessionOutcome)>> {
+    let mut command = prepared.command();
+    command.stdout(Stdio::null()).stderr(Stdio::inherit());
+    interrupt::isolate_command(&mut command);
+
+    options.emit(LaunchEvent::Spawning);
+    let mut watch = SessionWatch::start(options.on_interrupt, options.timeout);
+    let mut child = command.spawn()?;
+
+    log::info!("Minecraft launched with PID: {}", child.id());
+    options.emit(LaunchEvent::Spawned { pid: child.id() });
+
+    let _interrupts = interrupt::InterruptGuard::install();
+    let log_path = prepared.game_dir.join("logs").join("latest.log");
+    let Some(status) = tail::follow_log(&mut child, &log_path, &mut watch)? else {
+        return lock.hand_over(child.id()).map(|()| None);
+    };
+    drop(lock);
+    let outcome = watch.outcome(&prepared.game_dir, status);
+    options.emit(LaunchEvent::Exited { code: status.code(), outcome: outcome.clone() });
+
+    match status.code() {
+        Some(code) => println!("Minecraft exited with code {}", code),
+        None => println!("Minecraft exited ({})", status),
+    }
+
+    Ok(Some((status, outcome)))
+}
+
+// Passes the game output through unchanged until it closes, sending the first line that
+// shows the game is ready. The output is GB18030, so lines are only decoded for matching
+fn forward_output(stdout: impl Read, ready_sender: Sender<String>) -> io::Result<()> {
+    let mut reader = BufReader::new(stdout);
+    let mut console = io::stdout();
+    let mut line = Vec::new();
+    let mut ready = false;
+
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        // A closed console must not stop the game from being read
+        let _ = console.write_all(&line);
+
+        let text = String::from_utf8_lossy(&line);
+        if !ready && events::is_ready_line(&text) {
+            ready = true;
+            let _ = ready_sender.send(text.into_owned());
+        }
+        line.clear();
+    }
+
+    let _ = console.flush();
+    Ok(())
+}
+
+// Nothing is waited on, so no Exited event follows even with a handler set
+fn spawn_detached(prepared: &PreparedLaunch, options: &LaunchOptions, lock: lock::InstanceLock) -> io::Result<()> {
+    let mut command = prepared.command();
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    detach_command(&mut command);
+
+    options.emit(LaunchEvent::Spawning);
+    let child = command.spawn()?;
+
+    log::info!("Minecraft launched detached with PID: {}", child.id());
+    options.emit(LaunchEvent::Spawned { pid: child.id() });
+
+    lock.hand_over(child.id())
+}
+
+#[cfg(unix)]
+fn detach_command(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    // SAFETY: setsid is async-signal-safe and touches nothing of the parent's memory
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setsid() == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(windows)]
+fn detach_command(command: &mut Command) {
+    use std::os::windows::process::CommandExt;
+
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+
+    command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+}
+
+#[cfg(not(any(unix, windows)))]
+fn detach_command(_command: &mut Command) {}
+
+// Returns true when the game should run as the demo
+fn check_ownership(options: &LaunchOptions) -> Result<bool, WmmlError> {
+    let Some(access_token) = options.access_token.as_deref() else {
+        return Ok(false);
+    };
+
+    match profile::fetch_profile(Some(access_token)) {
+        Ok(_) => Ok(false),
+        // The session has no Java profile when the account never bought the game
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            if options.demo_if_unowned {
+                log::warn!("this account does not own Minecraft, launching the demo");
+                Ok(true)
+            } else {
+                Err(WmmlError::GameNotOwned)
+            }
+        }
+        Err(e) => {
+            log::warn!("could not verify game ownership: {}", e);
+            Ok(false)
+        }
+    }
+}
+
+// Never print the access token; it also appears inside the legacy session argument
+fn redact_command(command: &str, options: &LaunchOptions) -> String {
+    match options.access_token.as_deref() {
+        Some(token) if !token.is_empty() => command.replace(token, "***"),
+        _ => command.to_string(),
+    }
+}
+
+fn normalize_path(mc_path: &str) -> io::Result<PathBuf> {
+    let path = mc_path.replace('/', "\\");
+    /*if path == ".minecraft" {
+        let current_dir = ".minecarft"; //env::current_dir()?;  // 获取当前工作目录
+        Ok(current_dir.join(".minecraft"))
+    } else {*/
+        Ok(PathBuf::from(path))
+    //}
+}
+
+// Matches natives, natives-<os>-<arch> and <version>-natives-<n> as other launchers name them
+// A shared natives dir is used exactly as found, so it has to be there already
+fn check_prepared_natives(natives_dir: &Path, options: &LaunchOptions) -> io::Result<()> {
+    if !options.skip_natives_extraction {
+        return Ok(());
+    }
+
+    if options.natives_dir.is_none() {
+        log::warn!("natives extraction is skipped without --natives-dir, using {}", natives_dir.display());
+    }
+
+    let entries = fs::read_dir(natives_dir).map_err(|e| io::Error::new(
+        e.kind(),
+        format!("无法读取 natives 目录 {}: {}", natives_dir.display(), e)))?;
+    if entries.flatten().next().is_none() {
+        log::warn!("prepared natives dir {} is empty", natives_dir.display());
+    }
+
+    Ok(())
+}
+
+fn prune_natives_dirs(version_dir: &Path, keep: &Path) -> io::Result<usize> {
+    let mut removed = 0;
+
+    for entry in fs::read_dir(version_dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let is_natives = name == "natives" || name.starts_with("natives-") || name.contains("-natives");
+
+        if is_natives && entry.file_type()?.is_dir() && path != keep {
+            fs::remove_dir_all(&path)?;
+            removed += 1;
+        }
+    }
+
+    Ok(removed)
+}
+
+// Unpacks the native jars into the natives folder from scratch, for when files in it
+// were quarantined or half-written; returns the number of files written
+fn repair_natives(mc_path: &Path, layout: &Layout, version_name: &str) -> io::Result<usize> {
+    let version_json = resolve_version(mc_path, layout, version_name)?;
+    let platform = TargetPlatform::host();
+    let natives_dir = layout::natives_dir_for(mc_path, layout, version_name, platform);
+
+    if natives_dir.exists() {
+        fs::remove_dir_all(&natives_dir)?;
+    }
+
+    extract_natives(mc_path, layout, &version_json, platform, &natives_dir)
+}
+
+// Files already there with the right size are kept, so launches after the first write nothing;
+// returns the number of files written
+fn extract_natives(
+    mc_path: &Path,
+    layout: &Layout,
+    version_json: &VersionJson,
+    platform: &TargetPlatform,
+    natives_dir: &Path,
+) -> io::Result<usize> {
+    fs::create_dir_all(natives_dir)?;
+
+    let native_jars = version_json
+        .libraries
+        .iter()
+        .filter(|lib| native_key(lib).is_some() && check_library_rules(lib, platform))
+        .filter_map(|lib| get_library_path(mc_path, layout, lib, platform));
+
+    let mut extracted = 0;
+    for jar in native_jars {
+        let file = match fs::File::open(&jar) {
+            Ok(file) => file,
+            Err(e) => {
+                log::warn!("cannot read {}: {}, reinstall the version to restore it", jar.display(), e);
+                continue;
+            }
+        };
+        let mut archive = zip::ZipArchive::new(file)?;
+
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i)?;
+            let Some(name) = entry.enclosed_name() else {
+                continue;
+            };
+            if entry.is_dir() || name.starts_with("META-INF") {
+                continue;
+            }
+
+            let target = natives_dir.join(name);
+            if fs::metadata(&target).is_ok_and(|meta| meta.len() == entry.size()) {
+                continue;
+            }
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            io::copy(&mut entry, &mut fs::File::create(&target)?)?;
+            extracted += 1;
+        }
+    }
+
+    Ok(extracted)
+}
+
+fn select_java(mc_path: &Path, version_json: &VersionJson, options: &LaunchOptions) -> String {
+    let required_major = version_json.java_version.as_ref().and_then(|j| j.major_version);
+    if !options.auto_java {
+        return options.java_path(required_major);
+    }
+
+    let Some(component) = version_json.java_version.as_ref().and_then(|j| j.component.as_deref()) else {
+        let java_path = options.java_path(required_major);
+        log::warn!("{} does not name a Java runtime, using {}", version_json.id, java_path);
+        return java_path;
+    };
+
+    let progress = |pct| options.emit(LaunchEvent::Downloading { name: component.to_string(), pct });
+    match runtime::ensure_runtime(mc_path, component, progress) {
+        Ok(java) => java.to_string_lossy().into_owned(),
+        Err(e) => {
+            let java_path = options.java_path(required_major);
+            log::warn!("failed to provision Java runtime {}, using {}: {}", component, java_path, e);
+            java_path
+        }
+    }
+}
+
+// Max heap in MB, None when the JVM picks it
+fn launch_memory(version_json: &VersionJson, game_dir: &Path, options: &LaunchOptions) -> Option<u32> {
+    if options.use_system_memory {
+        return None;
+    }
+    if !options.auto_memory {
+        return options.memory;
+    }
+
+    let Some(total) = memory::total_memory_mb() else {
+        log::warn!("cannot tell the installed memory, using {}M", options.memory.unwrap_or(0));
+        return options.memory;
+    };
+
+    let modded = options.modded.unwrap_or_else(|| memory::is_modded(version_json, game_dir));
+    let memory = memory::recommended_memory(total, modded);
+    log::info!(
+        "Using {}M of {}M memory for a {} instance",
+        memory,
+        total,
+        if modded { "modded" } else { "vanilla" }
+    );
+
+    Some(memory)
+}
+
+// Returns the major version when the Java could be run
+fn check_java(java_path: &str, memory: Option<u32>) -> Option<u32> {
+    let java = match java::detect_java(java_path) {
+        Ok(java) => java,
+        Err(e) => {
+            log::warn!("{}", e);
+            return None;
+        }
+    };
+    let major = java::major_version(&java.version);
+
+    log::info!(
+        "Using Java {} ({}, {}-bit)",
+        java.version,
+        java.arch,
+        if java.is_64bit { 64 } else { 32 }
+    );
+
+    if java.is_64bit {
+        return major;
+    }
+
+    if let Some(memory) = memory {
+        if memory > java::MAX_32BIT_HEAP_MB {
+            log::warn!(
+                "32-bit Java cannot allocate -Xmx{}M (about {}M at most), use a 64-bit Java or lower the memory",
+                memory,
+                java::MAX_32BIT_HEAP_MB
+            );
+        }
+    }
+
+    log::warn!("32-bit Java cannot load the 64-bit natives in {}", layout::natives_dir_name(TargetPlatform::host()));
+    major
+}
+
+fn ensure_writable(dir: &Path) -> Result<(), WmmlError> {
+    let probe = dir.join(".wmml-write-test");
+
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            Ok(())
+        }
+        Err(e) if matches!(
+            e.kind(),
+            io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem
+        ) => Err(WmmlError::ReadOnlyDirectory(dir.to_path_buf())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn version_json_path(mc_path: &Path, layout: &Layout, version_name: &str) -> PathBuf {
+    layout
+        .version_dir(mc_path, version_name)
+        .join(format!("{}.json", version_name))
+}
+
+fn resolve_version(mc_path: &Path, layout: &Layout, version_name: &str) -> io::Result<VersionJson> {
+    let mut version_json = read_version(mc_path, layout, version_name)?;
+    let mut seen = vec![version_name.to_string()];
+
+    while let Some(parent_id) = version_json.inherits_from.take() {
+        if seen.contains(&parent_id) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("版本继承关系存在循环: {}", parent_id)));
+        }
+
+        let parent = read_version(mc_path, layout, &parent_id)?;
+        seen.push(parent_id);
+        version_json = merge_version_json(version_json, parent);
+    }
+
+    Ok(version_json)
+}
+
+// Child values win; libraries keep the child's first and game arguments append to the parent's
+fn merge_version_json(child: VersionJson, parent: VersionJson) -> VersionJson {
+    // The same native listed by both would be loaded twice; keep only the child's
+    let child_natives: HashSet<String> = child.libraries.iter().filter_map(native_key).collect();
+
+    let mut libraries = child.libraries;
+    libraries.extend(
+        parent
+            .libraries
+            .into_iter()
+            .filter(|lib| native_key(lib).is_none_or(|key| !child_natives.contains(&key))),
+    );
+
+    let arguments = match (parent.arguments, child.arguments) {
+        (Some(mut parent_args), Some(child_args)) => {
+            parent_args.game.extend(child_args.game);
+            parent_args.jvm.extend(child_args.jvm);
+            Some(parent_args)
+        }
+        (parent_args, child_args) => child_args.or(parent_args),
+    };
+
+    VersionJson {
+        id: child.id,
+        main_class: child.main_class,
+        minecraft_arguments: child.minecraft_arguments.or(parent.minecraft_arguments),
+        arguments,
+        libraries,
+        assets: child.assets.or(parent.assets),
+        asset_index: child.asset_index.or(parent.asset_index),
+        version_type: child.version_type.or(parent.version_type),
+        logging: child.logging.or(parent.logging),
+        java_version: child.java_version.or(parent.java_version),
+        inherits_from: parent.inherits_from,
+        // Without its own jar the child runs on the parent's client jar
+        jar: child.jar.or(parent.jar).or(Some(parent.id)),
+        // Both JSONs have to be understood
+        minimum_launcher_version: child.minimum_launcher_version.max(parent.minimum_launcher_version),
+        release_time: child.release_time.or(parent.release_time),
+        time: child.time.or(parent.time),
+    }
+}
+
+// Identifies a native artifact independent of its version
+fn native_key(lib: &Library) -> Option<String> {
+    let parts: Vec<&str> = lib.name.split(':').collect();
+    if parts.len() < 3 {
+        return None;
+    }
+
+    match (parts.get(3), &lib.natives) {
+        (Some(classifier), _) if classifier.starts_with("natives-") => {
+            Some(format!("{}:{}:{}", parts[0], parts[1], classifier))
+        }
+        (None, Some(_)) => Some(format!("{}:{}", parts[0], parts[1])),
+        _ => None,
+    }
+}
+
+fn client_jar_path(mc_path: &Path, layout: &Layout, version_json: &VersionJson) -> PathBuf {
+    let jar = version_json.jar.as_deref().unwrap_or(&version_json.id);
+
+    layout.version_dir(mc_path, jar).join(format!("{}.jar", jar))
+}
+
+fn read_version(mc_path: &Path, layout: &Layout, version_name: &str) -> io::Result<VersionJson> {
+    let json_path = version_json_path(mc_path, layout, version_name);
+    let jar_path = layout
+        .version_dir(mc_path, version_name)
+        .join(format!("{}.jar", version_name));
+
+    // Stripped installs only ship the client jar
+    if !json_path.exists() && jar_path.exists() {
+        return read_embedded_version_json(&jar_path, version_name);
+    }
+
+    read_version_json(&json_path)
+}
+
+#[derive(Deserialize)]
+struct EmbeddedVersion {
+    id: String,
+    stable: Option<bool>,
+    java_component: Option<String>,
+    java_version: Option<u32>,
+}
+
+// Client jars since 1.14 carry a version.json with the id but no libraries or asset index,
+// so this only gets a game going when everything it needs is already in place
+fn read_embedded_version_json(jar_path: &Path, version_name: &str) -> io::Result<VersionJson> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(jar_path)?)?;
+    let mut content = String::new();
+    archive
+        .by_name("version.json")
+        .map_err(|e| io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("缺少版本 JSON，且 {} 中没有 version.json: {}", jar_path.display(), e)))?
+        .read_to_string(&mut content)?;
+
+    let embedded: EmbeddedVersion = from_str(&content).map_err(|e| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("无效的JSON格式 {}: {}", jar_path.display(), e)))?;
+
+    log::warn!(
+        "no version JSON for {}, using the one embedded in the client jar without libraries or assets",
+        embedded.id
+    );
+
+    Ok(VersionJson {
+        id: embedded.id,
+        main_class: "net.minecraft.client.main.Main".to_string(),
+        minecraft_arguments: Some(
+            "--username ${auth_player_name} --version ${version_name} --gameDir ${game_directory} \
+             --assetsDir ${assets_root} --assetIndex ${assets_index_name} --uuid ${auth_uuid} \
+             --accessToken ${auth_access_token} --userType ${user_type} --versionType ${version_type}"
+                .to_string(),
+        ),
+        arguments: None,
+        libraries: Vec::new(),
+        assets: None,
+        asset_index: None,
+        version_type: Some(if embedded.stable.unwrap_or(true) { "release" } else { "snapshot" }.to_string()),
+        logging: None,
+        java_version: Some(JavaVersion {
+            component: embedded.java_component,
+            major_version: embedded.java_version,
+        }),
+        inherits_from: None,
+        // The folder may be named differently from the embedded id
+        jar: Some(version_name.to_string()),
+        minimum_launcher_version: None,
+        release_time: None,
+        time: None,
+    })
+}
+
+fn read_version_json(path: &Path) -> io::Result<VersionJson> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| io::Error::new(io::ErrorKind::NotFound, 
+            format!("无法读取文件 {}: {}", path.display(), e)))?;
+    
+    parse_version_json(&content).map_err(|e| io::Error::new(
+        io::ErrorKind::InvalidData, 
+        format!("无效的JSON格式 {}: {}", path.display(), e)))
+}
+
+fn parse_version_json(content: &str) -> serde_json::Result<VersionJson> {
+    from_str(content)
+}
+
+fn build_libraries_path(
+    mc_path: &Path,
+    layout: &Layout,
+    version_name: &str,
+    version_json: &VersionJson,
+    module_path: &[String],
+    options: &LaunchOptions,
+) -> io::Result<String> {
+    // Listing a module on the classpath too makes the JVM load its packages twice
+    let module_path: HashSet<String> = module_path.iter().map(|p| p.replace('\\', "/")).collect();
+
+    let classpath = launch_classpath(mc_path, layout, version_name, version_json)
+        .into_iter()
+        .filter(|p| p.exists())
+        .filter(|p| !module_path.contains(&p.to_string_lossy().replace('\\', "/")))
+        .collect();
+
+    Ok(join_classpath(classpath, options))
+}
+
+fn join_classpath(mut classpath: Vec<PathBuf>, options: &LaunchOptions) -> String {
+    // Given relative to WMML's directory, which is not the game's
+    let extra = options
+        .extra_classpath
+        .iter()
+        .map(|p| path::absolute(p).unwrap_or_else(|_| p.clone()));
+
+    if options.prepend_extra_classpath {
+        classpath.splice(0..0, extra);
+    } else {
+        classpath.extend(extra);
+    }
+
+    classpath
+        .iter()
+        .map(|p| p.to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join(CLASSPATH_SEPARATOR)
+}
+
+// collect_classpath for this machine, cached per version since big modpacks take a while to resolve.
+// The cache holds what the rules select, so files appearing or going away only matter afterwards
+fn launch_classpath(mc_path: &Path, layout: &Layout, version_name: &str, version_json: &VersionJson) -> Vec<PathBuf> {
+    let platform = TargetPlatform::host();
+
+    classpath_cache::cached_classpath(mc_path, layout, version_name, platform, || {
+        collect_classpath(mc_path, layout, version_json, platform)
+    })
+}
+
+// Every entry the version expects on the classpath, whether or not it is on disk
+fn collect_classpath(mc_path: &Path, layout: &Layout, version_json: &VersionJson, platform: &TargetPlatform) -> Vec<PathBuf> {
+    let mut classpath = vec![client_jar_path(mc_path, layout, version_json)];
+
+    classpath.extend(
+        version_json
+            .libraries
+            .iter()
+            .filter(|lib| check_library_rules(lib, platform))
+            .filter_map(|lib| get_library_path(mc_path, layout, lib, platform)),
+    );
+
+    classpath
+}
+
+fn check_main_class(classpath: &[PathBuf], main_class: &str) {
+    let entry = format!("{}.class", main_class.replace('.', "/"));
+
+    let found = classpath.iter().any(|path| {
+        if path.is_dir() {
+            return path.join(&entry).is_file();
+        }
+
+        fs::File::open(path)
+            .ok()
+            .and_then(|file| zip::ZipArchive::new(file).ok())
+            .is_some_and(|archive| archive.index_for_name(&entry).is_some())
+    });
+
+    if !found {
+        log::warn!(
+            "main class {} is not in any of the {} classpath entries, the game will fail with ClassNotFoundException",
+            main_class,
+            classpath.len()
+        );
+    }
+}
+
+// The last rule whose os applies decides, as for arguments, so a rule for another OS can no longer
+// undo an earlier allow and a library allowed on linux still picks natives-linux there
+fn check_library_rules(lib: &Library, platform: &TargetPlatform) -> bool {
+    match &lib.rules {
+        Some(rules) if !rules.is_empty() => check_argument_rules(rules, &[], platform),
+        _ => true,
+    }
+}
+
+fn get_library_path(mc_path: &Path, layout: &Layout, lib: &Library, platform: &TargetPlatform) -> Option<PathBuf> {
+    let parts: Vec<&str> = lib.name.split(':').collect();
+    if parts.len() < 3 {
+        return None;
+    }
+
+    let group_path = parts[0].replace('.', std::path::MAIN_SEPARATOR_STR);
+    let artifact_id = parts[1];
+    let version = parts[2];
+
+    let base_path = layout
+        .libraries_dir(mc_path)
+        .join(group_path)
+        .join(artifact_id)
+        .join(version);
+    let base_file = format!("{}-{}", artifact_id, version);
+
+    // Native-only artifacts carry the classifier in the name (e.g. org.lwjgl:lwjgl:3.3.1:natives-windows)
+    // and have no base jar next to them
+    if let Some(classifier) = parts.get(3) {
+        return Some(base_path.join(format!("{}-{}.jar", base_file, classifier)));
+    }
+
+    // The natives map alone picks the jar, so the choice is the same before and after a download;
+    // the rules have already told whether the library applies to this OS at all
+    if let Some(native) = lib.natives.as_ref().and_then(|natives| natives.get(&platform.os)) {
+        let classifier = native.replace("${arch}", platform.arch_bits());
+        return Some(base_path.join(format!("{}-{}.jar", base_file, classifier)));
+    }
+
+    Some(base_path.join(format!("{}.jar", base_file)))
+}
+
+fn build_game_arguments_vec(
+    assets_path: &Path,
+    game_dir: &Path,
+    version_name: &str,
+    player_name: &str,
+    version_json: &VersionJson,
+    demo: bool,
+    options: &LaunchOptions,
+) -> Vec<String> {
+    // Some third-party JSONs only carry assetIndex
+    let assets_index = version_json
+        .assets
+        .as_deref()
+        .or(version_json.asset_index.as_ref().map(|index| index.id.as_str()))
+        .unwrap_or("");
+
+    let mut args = Vec::new();
+    let mut features = Vec::new();
+
+    // Quick play is only emitted when the version declares the matching argument
+    if let Some(quick_play) = &options.quick_play {
+        if supports_feature(version_json, quick_play.feature()) {
+            features.push(quick_play.feature());
+        } else {
+            log::warn!(
+                "{} does not support {}, launching without quick play",
+                version_name,
+                quick_play.feature()
+            );
+        }
+    }
+    if demo {
+        features.push("is_demo_user");
+    }
+    let quick_play_target = options.quick_play.as_ref().map_or("", QuickPlay::target);
+    let access_token = options.access_token.as_deref().unwrap_or(OFFLINE_ACCESS_TOKEN);
+    let account_type = options.account_type.unwrap_or(match options.access_token {
+        Some(_) => AccountType::Msa,
+        None => AccountType::Legacy,
+    });
+    let uuid = options.uuid.clone().unwrap_or_else(|| offline::offline_uuid(player_name));
+    // Versions before 1.7.3 read loose files through --assetsDir ${game_assets} instead of
+    // --assetsDir ${assets_root} --assetIndex ${assets_index_name}
+    let game_assets = assets_path.join("virtual").join(assets_index);
+
+    // Relative quick play paths are kept inside the game dir. The game records quick play
+    // sessions there but does not create the folder
+    let quick_play_path = options
+        .quick_play_path
+        .as_ref()
+        .map(|p| game_dir.join(p))
+        .filter(|path| match path.parent().map(fs::create_dir_all) {
+            Some(Err(e)) => {
+                log::warn!("cannot create the folder of {}: {}, launching without --quickPlayPath", path.display(), e);
+                false
+            }
+            _ => true,
+        });
+    if quick_play_path.is_some() {
+        if supports_feature(version_json, "has_quick_plays_support") {
+            features.push("has_quick_plays_support");
+        } else {
+            log::warn!("{} does not support --quickPlayPath, ignoring it", version_name);
+        }
+    }
+
+    // Handle older versions with minecraftArguments
+    if let Some(minecraft_args) = &version_json.minecraft_arguments {
+        args.extend(minecraft_args.split_whitespace().map(String::from));
+    }
+
+    // Handle newer versions with arguments.game
+    if let Some(arguments) = &version_json.arguments {
+        args.extend(evaluate_arguments(&arguments.game, &features, TargetPlatform::host()));
+    }
+
+    // minecraftArguments has no feature rules, the launcher always appended --demo itself
+    if demo && !args.iter().any(|arg| arg == "--demo") {
+        args.push("--demo".to_string());
+    }
+
+    // Replace placeholders per token so values containing spaces stay one argument
+    let replacements = [
+        ("${auth_player_name}", player_name),
+        ("${version_name}", version_name),
+        ("${game_directory}", game_dir.to_str().unwrap_or("")),
+        ("${assets_root}", assets_path.to_str().unwrap_or("")),
+        ("${assets_index_name}", assets_index),
+        ("${game_assets}", game_assets.to_str().unwrap_or("")),
+        ("${auth_uuid}", &uuid),
+        ("${auth_access_token}", access_token),
+        ("${auth_session}", access_token),
+        ("${user_type}", account_type.user_type()),
+        ("${version_type}", "WMML 0.1.26"),
+        // 1.7.x passes Twitch credentials this way and fails to parse an empty value
+        ("${user_properties}", "{}"),
+        ("${quickPlaySingleplayer}", quick_play_target),
+        ("${quickPlayMultiplayer}", quick_play_target),
+        ("${quickPlayRealms}", quick_play_target),
+        (
+            "${quickPlayPath}",
+            quick_play_path.as_deref().and_then(Path::to_str).unwrap_or(""),
+        ),
+    ];
+
+    for arg in &mut args {
+        for (placeholder, value) in replacements {
+            *arg = arg.replace(placeholder, value);
+        }
+    }
+
+    drop_unresolved_arguments(args)
+}
+
+// The game looks up the SRV record itself and greets the server with the name as typed, which
+// servers behind a proxy route by, so the address is passed on unchanged. A failed lookup
+// here never stops the launch
+fn report_server_address(address: &str) {
+    match srv::resolve_server_address(address) {
+        Ok(Some((host, port))) => log::info!("{} points to {}:{}", address, host, port),
+        Ok(None) => {}
+        Err(e) => log::warn!("cannot look up the SRV record of {}: {}", address, e),
+    }
+}
+
+// Leaves out options whose value is a placeholder WMML cannot fill, such as ${clientid} or
+// ${auth_xuid}, instead of passing the literal text; the game treats them all as optional
+fn drop_unresolved_arguments(args: Vec<String>) -> Vec<String> {
+    let unresolved = |arg: &str| arg.starts_with("${") && arg.ends_with('}');
+    let mut kept: Vec<String> = Vec::with_capacity(args.len());
+
+    for arg in args {
+        if !unresolved(&arg) {
+            kept.push(arg);
+            continue;
+        }
+
+        log::debug!("leaving out game argument {} without a value", arg);
+        if kept.last().is_some_and(|option| option.starts_with("--")) {
+            kept.pop();
+        }
+    }
+
+    kept
+}
+
+fn argument_value(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+// Expands plain and rule-gated entries of arguments.game / arguments.jvm
+fn evaluate_arguments(arguments: &[GameArgument], features: &[&str], platform: &TargetPlatform) -> Vec<String> {
+    let mut args = Vec::new();
+
+    for arg in arguments {
+        match arg {
+            GameArgument::String(s) => args.push(s.clone()),
+            GameArgument::Object(object) => {
+                if !check_argument_rules(&argument_rules(object), features, platform) {
+                    continue;
+                }
+
+                match object.get("value") {
+                    Some(Value::String(s)) => args.push(s.clone()),
+                    Some(Value::Array(values)) => args.extend(values.iter().filter_map(argument_value)),
+                    Some(value) => args.extend(argument_value(value)),
+                    None => {}
+                }
+            }
+        }
+    }
+
+    args
+}
+
+fn os_version_matches(os: &Os, platform: &TargetPlatform) -> bool {
+    let Some(pattern) = &os.version else {
+        return true;
+    };
+
+    match (regex::Regex::new(pattern), platform.os_version.as_deref()) {
+        (Ok(regex), Some(version)) => regex.is_match(version),
+        (Err(e), _) => {
+            log::warn!("invalid os.version rule {}: {}", pattern, e);
+            false
+        }
+        (_, None) => false,
+    }
+}
+
+fn argument_rules(object: &HashMap<String, Value>) -> Vec<Rule> {
+    object
+        .get("rules")
+        .and_then(|rules| serde_json::from_value(rules.clone()).ok())
+        .unwrap_or_default()
+}
+
+fn check_argument_rules(rules: &[Rule], features: &[&str], platform: &TargetPlatform) -> bool {
+    let mut allowed = false;
+
+    for rule in rules {
+        let os_matches = rule.os.as_ref().is_none_or(|os| {
+            os.name.as_deref().is_none_or(|name| name == platform.os)
+                && os.arch.as_deref().is_none_or(|arch| arch == platform.arch)
+                && os_version_matches(os, platform)
+        });
+        let features_match = rule.features.as_ref().is_none_or(|required| {
+            required
+                .iter()
+                .all(|(name, enabled)| features.contains(&name.as_str()) == *enabled)
+        });
+
+        if os_matches && features_match {
+            allowed = rule.action == "allow";
+        }
+    }
+
+    allowed
+}
+
+fn supports_feature(version_json: &VersionJson, feature: &str) -> bool {
+    let Some(arguments) = &version_json.arguments else {
+        return false;
+    };
+
+    arguments.game.iter().any(|arg| match arg {
+        GameArgument::Object(object) => argument_rules(object).iter().any(|rule| {
+            rule.features.as_ref().is_some_and(|f| f.contains_key(feature))
+        }),
+        GameArgument::String(_) => false,
+    })
+}
+
+fn prepare_log_config(
+    mc_path: &Path,
+    layout: &Layout,
+    version_name: &str,
+    version_json: &VersionJson,
+    options: &LaunchOptions,
+) -> PathBuf {
+    let version_dir = layout.version_dir(mc_path, version_name);
+    let default_path = version_dir.join("log4j2.xml");
+
+    let Some(client) = version_json.logging.as_ref().and_then(|l| l.client.as_ref()) else {
+        return default_path;
+    };
+
+    let path = version_dir.join(&client.file.id);
+    let up_to_date = fs::metadata(&path)
+        .is_ok_and(|m| client.file.size.is_none_or(|size| m.len() == size));
+
+    if !up_to_date {
+        println!("Downloading logging config {}", client.file.id);
+
+        let result = download::download_file_with_progress(
+            &client.file.url,
+            &path,
+            client.file.sha1.as_deref(),
+            |pct| options.emit(LaunchEvent::Downloading { name: client.file.id.clone(), pct }),
+        );
+
+        if let Err(e) = result {
+            log::warn!("failed to download logging config: {}", e);
+            return default_path;
+        }
+    }
+
+    path
+}
+
+fn build_version_jvm_arguments(
+    libraries_dir: &Path,
+    natives_dir: &Path,
+    version_name: &str,
+    version_json: &VersionJson,
+) -> Vec<String> {
+    let Some(arguments) = &version_json.arguments else {
+        return Vec::new();
+    };
+
+    let mut raw_args = evaluate_arguments(&arguments.jvm, &[], TargetPlatform::host());
+
+    // The launcher adds -cp itself, after these
+    if let Some(i) = raw_args.iter().position(|arg| arg == "${classpath}") {
+        raw_args.remove(i);
+        if i > 0 && matches!(raw_args[i - 1].as_str(), "-cp" | "-classpath" | "--class-path") {
+            raw_args.remove(i - 1);
+        }
+    }
+
+    let replacements = [
+        ("${natives_directory}", natives_dir.to_str().unwrap_or("")),
+        ("${library_directory}", libraries_dir.to_str().unwrap_or("")),
+        ("${classpath_separator}", CLASSPATH_SEPARATOR),
+        ("${launcher_name}", "WMML"),
+        ("${launcher_version}", "0.1.26"),
+        ("${version_name}", version_name),
+    ];
+
+    for arg in &mut raw_args {
+        for (placeholder, value) in replacements {
+            *arg = arg.replace(placeholder, value);
+        }
+    }
+
+    raw_args
+}
+
+// Jars passed with -p / --module-path
+fn module_path_entries(jvm_args: &[String]) -> Vec<String> {
+    let mut entries = Vec::new();
+    let mut args = jvm_args.iter();
+
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "-p" | "--module-path" => args.next().map(String::as_str),
+            other => other.strip_prefix("--module-path="),
+        };
+
+        if let Some(value) = value {
+            entries.extend(value.split(CLASSPATH_SEPARATOR).filter(|p| !p.is_empty()).map(String::from));
+        }
+    }
+
+    entries
+}
+
+// Files the default JVM flags point at
+struct JvmPaths<'a> {
+    natives_dir: &'a Path,
+    client_jar: &'a Path,
+    log_config: &'a Path,
+    // logging.client.argument of the version, -Dlog4j.configurationFile=${path} when unset
+    log_config_argument: Option<&'a str>,
+    heap_dump: Option<&'a Path>,
+}
+
+fn locale_properties(options: &LaunchOptions) -> Vec<String> {
+    let Some((language, country)) = options.locale.as_deref().and_then(game_options::parse_locale) else {
+        return Vec::new();
+    };
+
+    let mut properties = vec![format!("-Duser.language={}", language)];
+    if let Some(country) = country {
+        properties.push(format!("-Duser.country={}", country));
+    }
+
+    properties
+}
+
+// Both only listen on the loopback interface; Java 8 binds a bare JDWP port on every interface
+fn debug_arguments(options: &LaunchOptions) -> Vec<String> {
+    let mut arguments = Vec::new();
+
+    if let Some(port) = options.debug_port {
+        let suspend = if options.debug_suspend { "y" } else { "n" };
+        arguments.push(format!("-agentlib:jdwp=transport=dt_socket,server=y,suspend={},address=127.0.0.1:{}", suspend, port));
+    } else if options.debug_suspend {
+        log::warn!("--debug-suspend has no effect without --debug-port");
+    }
+
+    if let Some(port) = options.jmx_port {
+        arguments.extend([
+            format!("-Dcom.sun.management.jmxremote.port={}", port),
+            format!("-Dcom.sun.management.jmxremote.rmi.port={}", port),
+            "-Dcom.sun.management.jmxremote.host=127.0.0.1".to_string(),
+            "-Djava.rmi.server.hostname=127.0.0.1".to_string(),
+            "-Dcom.sun.management.jmxremote.authenticate=false".to_string(),
+            "-Dcom.sun.management.jmxremote.ssl=false".to_string(),
+        ]);
+    }
+
+    arguments
+}
+
+fn build_jvm_arguments(
+    paths: &JvmPaths,
+    libraries: &str,
+    version_jvm_args: &[String],
+    user_jvm_args: &[String],
+    memory: Option<u32>,
+    options: &LaunchOptions,
+) -> Vec<String> {
+    let mut flags = Vec::new();
+
+    // Memory settings; an auto-sized heap may come out below a fixed minimum
+    if let Some(memory) = memory {
+        flags.push(format!("-Xmx{}M", memory));
+        flags.push(format!("-Xms{}M", options.min_memory.unwrap_or(memory).min(memory)));
+    }
+
+    // Common JVM arguments
+    flags.extend([
+        "-Dfile.encoding=GB18030",
+        "-Dsun.stdout.encoding=GB18030",
+        "-Dsun.stderr.encoding=GB18030",
+    ].map(String::from));
+
+    flags.extend(locale_properties(options));
+    flags.extend(debug_arguments(options));
+    // Clients from before authlib never read these
+    flags.extend(options.api_endpoints.properties());
+
+    if !options.no_log4j_mitigation {
+        flags.extend([
+            "-Djava.rmi.server.useCodebaseOnly=true",
+            "-Dcom.sun.jndi.rmi.object.trustURLCodebase=false",
+            "-Dcom.sun.jndi.cosnaming.object.trustURLCodebase=false",
+            "-Dlog4j2.formatMsgNoLookups=true",
+        ].map(String::from));
+    }
+
+    let log_config = paths.log_config.to_str().unwrap_or("");
+    flags.push(match paths.log_config_argument {
+        Some(argument) => argument.replace("${path}", log_config),
+        None => format!("-Dlog4j.configurationFile={}", log_config),
+    });
+
+    flags.extend([
+        &format!(
+            "-Dminecraft.client.jar={}",
+            paths.client_jar.to_str().unwrap_or("")
+        ),
+        "-XX:+UnlockExperimentalVMOptions",
+        "-XX:+UseG1GC",
+        "-XX:G1NewSizePercent=20",
+        "-XX:G1ReservePercent=20",
+        "-XX:MaxGCPauseMillis=50",
+        "-XX:G1HeapRegionSize=32m",
+        "-XX:-UseAdaptiveSizePolicy",
+        "-XX:-OmitStackTraceInFastThrow",
+        "-XX:-DontCompileHugeMethods",
+        "-Dfml.ignoreInvalidMinecraftCertificates=true",
+        "-Dfml.ignorePatchDiscrepancies=true",
+    ].map(String::from));
+
+    if let (Some(title), "osx") = (&options.window_title, TargetPlatform::host().os.as_str()) {
+        flags.push(format!("-Xdock:name={}", title));
+    }
+
+    // Intel drivers key their Minecraft fixes on this file name appearing in the command line
+    if let Some(heap_dump) = paths.heap_dump {
+        flags.push(format!("-XX:HeapDumpPath={}", heap_dump.display()));
+    }
+
+    // Everything that loads or unpacks native code is pointed at the one natives folder
+    let natives_dir = paths.natives_dir.to_str().unwrap_or("");
+    flags.extend(
+        ["java.library.path", "jna.tmpdir", "org.lwjgl.system.SharedLibraryExtractPath", "io.netty.native.workdir"]
+            .map(|property| format!("-D{}={}", property, natives_dir)),
+    );
+
+    flags.extend([
+        "-Dminecraft.launcher.brand=WMML",
+        "-Dminecraft.launcher.version=0.1.26",
+    ].map(String::from));
+
+    let flags = merge_jvm_args(flags, user_jvm_args);
+    let mut args = apply_jvm_flag_overrides(flags, &options.jvm_flags);
+
+    // The game needs these as they are, so nothing above overrides them
+    args.extend_from_slice(version_jvm_args);
+
+    args.push("-cp".to_string());
+    args.push(libraries.to_string());
+
+    args
+}
+
+// -XX:+Name, -XX:Name=value and -Dname=value are keyed by Name; -Xmx and friends by their prefix
+fn jvm_flag_key(flag: &str) -> &str {
+    if let Some(option) = flag.strip_prefix("-XX:") {
+        option.trim_start_matches(['+', '-']).split('=').next().unwrap_or(option)
+    } else if let Some(property) = flag.strip_prefix("-D") {
+        property.split('=').next().unwrap_or(property)
+    } else if ["-Xmx", "-Xms", "-Xss", "-Xmn"].iter().any(|prefix| flag.starts_with(prefix)) {
+        &flag[1..4]
+    } else {
+        flag.split('=').next().unwrap_or(flag)
+    }
+}
+
+fn apply_jvm_flag_overrides(flags: Vec<String>, overrides: &[(String, Option<String>)]) -> Vec<String> {
+    let mut keyed: Vec<(String, String)> = flags
+        .into_iter()
+        .map(|flag| (jvm_flag_key(&flag).to_string(), flag))
+        .collect();
+
+    for (key, flag) in overrides {
+        match (keyed.iter().position(|(k, _)| k == key), flag) {
+            (Some(index), Some(flag)) => keyed[index].1 = flag.clone(),
+            (Some(index), None) => {
+                keyed.remove(index);
+            }
+            (None, Some(flag)) => keyed.push((key.clone(), flag.clone())),
+            (None, None) => log::warn!("no default JVM flag {} to remove", key),
+        }
+    }
+
+    keyed.into_iter().map(|(_, flag)| flag).collect()
+}
+
+fn java_arguments(
+    java_path: &str,
+    jvm_args: &[String],
+    main_class: &str,
+    game_args: &[String],
+) -> Vec<String> {
+    let mut args = vec![java_path.to_string()];
+    args.extend_from_slice(jvm_args);
+    args.push(main_class.to_string());
+    args.extend_from_slice(game_args);
+
+    args
+}
+
+// JVM arguments apply in this order, each source replacing the -XX:, -D, -Xmx, -Xms, -Xss and
+// -Xmn flags of earlier ones by key and adding everything else:
+//   1. WMML's defaults and memory settings
+//   2. global: <mc_path>/.jvmargs
+//   3. profile: <version dir>/.jvmargs
+//   4. instance: <game dir>/.jvmargs, then user_jvm_args.txt written by Forge / NeoForge
+//   5. command line: --jvm-flag and --remove-jvm-flag
+// The version JSON's arguments.jvm follow unchanged
+fn merge_jvm_args(mut flags: Vec<String>, overrides: &[String]) -> Vec<String> {
+    for flag in overrides {
+        let existing = is_keyed_jvm_flag(flag)
+            .then(|| flags.iter().position(|existing| is_keyed_jvm_flag(existing) && jvm_flag_key(existing) == jvm_flag_key(flag)))
+            .flatten();
+
+        match existing {
+            Some(index) => flags[index] = flag.clone(),
+            None => flags.push(flag.clone()),
+        }
+    }
+
+    flags
+}
+
+// Options such as --add-opens take their value as the next argument and -javaagent may repeat,
+// so only flags that carry their own value and can only be set once are merged
+fn is_keyed_jvm_flag(flag: &str) -> bool {
+    flag.starts_with("-XX:")
+        || flag.starts_with("-D")
+        || ["-Xmx", "-Xms", "-Xss", "-Xmn"].iter().any(|prefix| flag.starts_with(prefix))
+}
+
+// Sources 2 to 4 of merge_jvm_args, each file read once when the game dir is mc_path
+fn read_jvm_args_files(mc_path: &Path, version_dir: Option<&Path>, game_dir: &Path) -> io::Result<Vec<String>> {
+    let mut paths = vec![mc_path.join(JVM_ARGS_FILE_NAME)];
+    paths.extend(version_dir.map(|version_dir| version_dir.join(JVM_ARGS_FILE_NAME)));
+    for path in [game_dir.join(JVM_ARGS_FILE_NAME), game_dir.join(USER_JVM_ARGS_NAME)] {
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+
+    let mut args = Vec::new();
+    for path in paths.iter().filter(|path| path.exists()) {
+        let content = fs::read_to_string(path).map_err(|e| io::Error::new(
+            e.kind(),
+            format!("无法读取文件 {}: {}", path.display(), e)))?;
+        let file_args = parse_argfile(&content);
+
+        println!("Applying {} JVM arguments from {}", file_args.len(), path.display());
+        args.extend(file_args);
+    }
+
+    Ok(args)
+}
+
+// Follows the JVM's @argfile rules: `#` before a token comments out the rest of the line,
+// quotes can be opened mid-token and end at the line end at the latest, backslash escapes
+// only apply inside quotes and a backslash before a line break continues the quoted token
+fn parse_argfile(content: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quote: Option<char> = None;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            let token = current.get_or_insert_with(String::new);
+            match c {
+                '\\' => match chars.next() {
+                    Some('n') => token.push('\n'),
+                    Some('r') => token.push('\r'),
+                    Some('t') => token.push('\t'),
+                    Some('f') => token.push('\x0c'),
+                    Some(line_break @ ('\n' | '\r')) => {
+                        if line_break == '\r' {
+                            chars.next_if_eq(&'\n');
+                        }
+                        while chars.next_if(|c| *c == ' ' || *c == '\t').is_some() {}
+                    }
+                    Some(other) => token.push(other),
+                    None => {}
+                },
+                '\n' | '\r' => {
+                    quote = None;
+                    args.extend(current.take());
+                }
+                c if c == q => quote = None,
+                c => token.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            ' ' | '\t' | '\n' | '\r' | '\x0c' => args.extend(current.take()),
+            '#' if current.is_none() => {
+                while chars.next_if(|c| *c != '\n' && *c != '\r').is_some() {}
+            }
+            '"' | '\'' => {
+                quote = Some(c);
+                current.get_or_insert_with(String::new);
+            }
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(current.take());
+
+    args
+}
+
+fn write_argfile(path: &Path, args: &[String]) -> io::Result<()> {
+    let content = args
+        .iter()
+        .map(|arg| quote_argfile_arg(arg))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    fs::write(path, content).map_err(|e| io::Error::new(
+        e.kind(),
+        format!("无法写入参数文件 {}: {}", path.display(), e)))
+}
+
+// Inside quotes the JVM treats backslashes as escapes, so Windows paths need doubling
+fn quote_argfile_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || c == '"' || c == '\'' || c == '#') {
+        return arg.to_string();
+    }
+
+    format!(
+        "\"{}\"",
+        arg.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\r', "\\r")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    fn prepared(args: &[&str], working_dir: &Path) -> PreparedLaunch {
+        PreparedLaunch {
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            jvm_args: Vec::new(),
+            game_dir: working_dir.to_path_buf(),
+            working_dir: working_dir.to_path_buf(),
+            env: Vec::new(),
+            title: None,
+        }
+    }
+
+    #[test]
+    fn java_arguments_keep_every_token_whole() {
+        let game_args = ["--username".to_string(), "Steve Jobs".to_string(), "--gameDir".to_string(), "C:\\My Games\\mc & co".to_string()];
+        let args = java_arguments("java", &["-Xmx2G".to_string()], "net.minecraft.client.main.Main", &game_args);
+
+        assert_eq!(args, ["java", "-Xmx2G", "net.minecraft.client.main.Main", "--username", "Steve Jobs", "--gameDir", "C:\\My Games\\mc & co"]);
+    }
+
+    #[test]
+    fn game_arguments_keep_names_and_paths_with_spaces_whole() {
+        let version_json = parse_version_json(r#"{
+            "id": "1.12.2",
+            "mainClass": "net.minecraft.client.main.Main",
+            "minecraftArguments": "--username ${auth_player_name} --gameDir ${game_directory} --assetIndex ${assets_index_name}",
+            "libraries": [],
+            "assets": "1.12"
+        }"#).unwrap();
+        let options = LaunchOptions::default();
+
+        let args = build_game_arguments_vec(Path::new("assets"), Path::new("/games/my instance"), "1.12.2", "Steve Jobs", &version_json, false, &options);
+
+        assert_eq!(args, ["--username", "Steve Jobs", "--gameDir", "/games/my instance", "--assetIndex", "1.12"]);
+    }
+
+    #[test]
+    fn quick_play_servers_reach_the_game_as_typed() {
+        let version_json = parse_version_json(r#"{
+            "id": "1.20.1",
+            "mainClass": "net.minecraft.client.main.Main",
+            "arguments": { "game": [
+                "--username", "${auth_player_name}",
+                { "rules": [{ "action": "allow", "features": { "is_quick_play_multiplayer": true } }], "value": ["--quickPlayMultiplayer", "${quickPlayMultiplayer}"] }
+            ] },
+            "libraries": []
+        }"#).unwrap();
+        let options = LaunchOptions { quick_play: Some(QuickPlay::Multiplayer("mc.example.invalid".to_string())), ..LaunchOptions::default() };
+
+        let args = build_game_arguments_vec(Path::new("assets"), Path::new("/mc"), "1.20.1", "Steve", &version_json, false, &options);
+
+        assert_eq!(args, ["--username", "Steve", "--quickPlayMultiplayer", "mc.example.invalid"]);
+    }
+
+    #[test]
+    fn asset_index_name_falls_back_to_the_asset_index_id() {
+        let version_json = parse_version_json(r#"{
+            "id": "third-party",
+            "mainClass": "net.minecraft.client.main.Main",
+            "minecraftArguments": "--assetsDir ${assets_root} --assetIndex ${assets_index_name}",
+            "libraries": [],
+            "assetIndex": { "id": "1.19", "sha1": "0", "size": 0, "url": "https://example.invalid/1.19.json" }
+        }"#).unwrap();
+        assert!(version_json.assets.is_none());
+
+        let args = build_game_arguments_vec(Path::new("/mc/assets"), Path::new("/mc"), "third-party", "Steve", &version_json, false, &LaunchOptions::default());
+
+        assert_eq!(args, ["--assetsDir", "/mc/assets", "--assetIndex", "1.19"]);
+    }
+
+    #[test]
+    fn merge_keeps_one_native_per_coordinate_preferring_the_child() {
+        let parent = parse_version_json(r#"{
+            "id": "1.19.2",
+            "mainClass": "net.minecraft.client.main.Main",
+            "libraries": [
+                { "name": "org.lwjgl:lwjgl:3.3.1" },
+                { "name": "org.lwjgl:lwjgl:3.3.1:natives-linux" },
+                { "name": "org.lwjgl:lwjgl:3.3.1:natives-windows" },
+                { "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4", "natives": { "linux": "natives-linux" } }
+            ]
+        }"#).unwrap();
+        let child = parse_version_json(r#"{
+            "id": "1.19.2-forge",
+            "mainClass": "cpw.mods.bootstraplauncher.BootstrapLauncher",
+            "inheritsFrom": "1.19.2",
+            "libraries": [
+                { "name": "org.lwjgl:lwjgl:3.3.3:natives-linux" },
+                { "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.5", "natives": { "linux": "natives-linux" } }
+            ]
+        }"#).unwrap();
+
+        let merged = merge_version_json(child, parent);
+        let names: Vec<&str> = merged.libraries.iter().map(|lib| lib.name.as_str()).collect();
+
+        assert_eq!(names, [
+            "org.lwjgl:lwjgl:3.3.3:natives-linux",
+            "org.lwjgl.lwjgl:lwjgl-platform:2.9.5",
+            "org.lwjgl:lwjgl:3.3.1",
+            "org.lwjgl:lwjgl:3.3.1:natives-windows",
+        ]);
+    }
+
+    #[test]
+    fn a_linux_only_library_picks_its_linux_natives() {
+        let lib: Library = serde_json::from_str(r#"{
+            "name": "org.lwjgl:lwjgl:3.3.1",
+            "rules": [{ "action": "allow", "os": { "name": "linux" } }],
+            "natives": { "linux": "natives-linux", "windows": "natives-windows-${arch}", "osx": "natives-macos" }
+        }"#).unwrap();
+        let linux = TargetPlatform::parse("linux-x86_64").unwrap();
+        let windows = TargetPlatform::parse("windows-x86_64").unwrap();
+
+        // Only the base jar on disk must not change the pick
+        let dir = tempfile::tempdir().unwrap();
+        let layout = Layout::default();
+        let base = layout.libraries_dir(dir.path()).join("org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1.jar");
+        fs::create_dir_all(base.parent().unwrap()).unwrap();
+        fs::write(&base, "").unwrap();
+
+        assert!(check_library_rules(&lib, &linux));
+        assert_eq!(get_library_path(dir.path(), &layout, &lib, &linux), Some(base.with_file_name("lwjgl-3.3.1-natives-linux.jar")));
+        assert!(!check_library_rules(&lib, &windows));
+    }
+
+    #[test]
+    fn natives_are_extracted_once_without_meta_inf() {
+        let version_json = parse_version_json(r#"{
+            "id": "1.12.2",
+            "mainClass": "net.minecraft.client.main.Main",
+            "libraries": [
+                { "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4", "natives": { "linux": "natives-linux", "windows": "natives-windows" } }
+            ]
+        }"#).unwrap();
+        let platform = TargetPlatform::parse("linux-x86_64").unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let layout = Layout::default();
+
+        let jar = layout.libraries_dir(dir.path()).join("org/lwjgl/lwjgl/lwjgl-platform/2.9.4/lwjgl-platform-2.9.4-natives-linux.jar");
+        fs::create_dir_all(jar.parent().unwrap()).unwrap();
+        let mut writer = zip::ZipWriter::new(fs::File::create(&jar).unwrap());
+        for name in ["liblwjgl64.so", "META-INF/MANIFEST.MF"] {
+            writer.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+            writer.write_all(b"native").unwrap();
+        }
+        writer.finish().unwrap();
+
+        let natives_dir = dir.path().join("natives");
+        assert_eq!(extract_natives(dir.path(), &layout, &version_json, &platform, &natives_dir).unwrap(), 1);
+        assert_eq!(fs::read(natives_dir.join("liblwjgl64.so")).unwrap(), b"native");
+        assert!(!natives_dir.join("META-INF").exists());
+
+        assert_eq!(extract_natives(dir.path(), &layout, &version_json, &platform, &natives_dir).unwrap(), 0);
+    }
+
+    #[test]
+    fn a_cached_classpath_picks_up_libraries_downloaded_after_it() {
+        let content = r#"{
+            "id": "1.12.2",
+            "mainClass": "net.minecraft.client.main.Main",
+            "libraries": [{ "name": "com.mojang:realms:1.10.22" }]
+        }"#;
+        let dir = tempfile::tempdir().unwrap();
+        let layout = Layout::default();
+        let json_path = version_json_path(dir.path(), &layout, "1.12.2");
+        fs::create_dir_all(json_path.parent().unwrap()).unwrap();
+        fs::write(&json_path, content).unwrap();
+        let version_json = parse_version_json(content).unwrap();
+        let options = LaunchOptions::default();
+
+        let before = build_libraries_path(dir.path(), &layout, "1.12.2", &version_json, &[], &options).unwrap();
+        assert!(layout.version_dir(dir.path(), "1.12.2").join(classpath_cache::CACHE_FILE_NAME).exists());
+
+        let realms = layout.libraries_dir(dir.path()).join(["com", "mojang", "realms", "1.10.22", "realms-1.10.22.jar"].iter().collect::<PathBuf>());
+        fs::create_dir_all(realms.parent().unwrap()).unwrap();
+        fs::write(&realms, "").unwrap();
+        let after = build_libraries_path(dir.path(), &layout, "1.12.2", &version_json, &[], &options).unwrap();
+
+        assert_eq!(before, "");
+        assert_eq!(after, realms.to_string_lossy());
+    }
+
+    #[test]
+    fn argfile_comments_blank_lines_and_quotes() {
+        let content = "# JVM arguments\n\n-Xmx4G   -XX:+UseG1GC # trailing comment\n\
+            \"-Dinstance=My Pack\"\n-Dpath='C:\\Games\\mc'\n-Dtag=a#b\n\
+            \"-Dlook=\\\"quoted\\\" \\\n    continued\"\n'unterminated quote\n\t\n";
+
+        assert_eq!(parse_argfile(content), [
+            "-Xmx4G",
+            "-XX:+UseG1GC",
+            "-Dinstance=My Pack",
+            "-Dpath=C:Gamesmc",
+            "-Dtag=a#b",
+            "-Dlook=\"quoted\" continued",
+            "unterminated quote",
+        ]);
+    }
+
+    #[test]
+    fn argfile_arguments_survive_a_round_trip() {
+        let args = [
+            "-Xmx4G",
+            "-Djava.library.path=C:\\Users\\Steve Jobs\\.minecraft\\natives",
+            "--username",
+            "Steve Jobs",
+            "# not a comment",
+            "it's \"quoted\"",
+            "line\nbreak",
+            "tab\there",
+            "",
+        ];
+
+        let content = args.iter().map(|arg| quote_argfile_arg(arg)).collect::<Vec<_>>().join("\n");
+
+        assert_eq!(quote_argfile_arg("-Xmx4G"), "-Xmx4G");
+        assert_eq!(parse_argfile(&content), args);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn launch_command_passes_arguments_unsplit() {
+        let dir = tempfile::tempdir().unwrap();
+        let prepared = prepared(&["sh", "-c", "printf '%s\\n' \"$@\"", "sh", "Steve Jobs", "a&b|c", "$HOME"], dir.path());
+
+        let output = prepared.command().output().unwrap();
+
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "Steve Jobs\na&b|c\n$HOME\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn printed_commands_hide_the_access_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let prepared = prepared(&["java", "--accessToken", "ey.secret-token", "--session", "token:ey.secret-token:0"], dir.path());
+        let options = LaunchOptions::builder().access_token("ey.secret-token").build();
+
+        let command = redact_command(&launch_command_string(&prepared), &options);
+
+        assert_eq!(command, "java --accessToken *** --session token:***:0");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn launch_command_reports_the_pid_of_the_program_itself() {
+        let dir = tempfile::tempdir().unwrap();
+        let prepared = prepared(&["sh", "-c", "echo $$"], dir.path());
+
+        let child = prepared.command().stdout(Stdio::piped()).spawn().unwrap();
+        let pid = child.id();
+        let output = child.wait_with_output().unwrap();
+
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), pid.to_string());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_detached_launch_hands_the_lock_to_the_game_itself() {
+        let dir = tempfile::tempdir().unwrap();
+        let prepared = prepared(&["sh", "-c", "echo $$ > launched.pid"], dir.path());
+        let lock = lock::InstanceLock::acquire(dir.path(), false).unwrap();
+
+        spawn_detached(&prepared, &LaunchOptions::default(), lock).unwrap();
+
+        let launched = dir.path().join("launched.pid");
+        let deadline = std::time::Instant::now() + Duration::from_secs(10);
+        while fs::read_to_string(&launched).map_or(true, |pid| !pid.ends_with('\n')) {
+            assert!(std::time::Instant::now() < deadline, "the detached program never ran");
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(fs::read_to_string(&launched).unwrap().trim(), fs::read_to_string(dir.path().join(".wmml.lock")).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_detached_game_leads_its_own_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut command = prepared(&["sleep", "30"], dir.path()).command();
+        detach_command(&mut command);
+
+        let mut child = command.spawn().unwrap();
+        // SAFETY: getsid only reads the session of a process
+        let (session, own_session) = unsafe { (libc::getsid(child.id() as libc::pid_t), libc::getsid(0)) };
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        assert_eq!(session, child.id() as libc::pid_t);
+        assert_ne!(session, own_session);
+    }
+}
@@ -0,0 +1,122 @@
+use std::{
+    fs,
+    io,
+    path::Path,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::from_str;
+
+use crate::{download, mirror::Mirror};
+
+const MANIFEST_URL: &str = "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
+const MANIFEST_CACHE_NAME: &str = "version_manifest_v2.json";
+const MANIFEST_VALIDATORS_NAME: &str = "version_manifest_v2.cache.json";
+
+#[derive(Debug, Deserialize)]
+pub struct VersionManifest {
+    pub latest: LatestVersions,
+    pub versions: Vec<ManifestVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LatestVersions {
+    pub release: String,
+    pub snapshot: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ManifestVersion {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub version_type: String,
+    pub url: String,
+    // Only present in the v2 manifest
+    pub sha1: Option<String>,
+}
+
+// HTTP validators of the cached manifest, sent back as conditional request headers
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheValidators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+pub fn fetch_version_manifest(mc_path: &Path) -> io::Result<VersionManifest> {
+    let cache_path = mc_path.join(MANIFEST_CACHE_NAME);
+    let validators_path = mc_path.join(MANIFEST_VALIDATORS_NAME);
+
+    let cached = fs::read_to_string(&cache_path).ok();
+    let validators: CacheValidators = fs::read_to_string(&validators_path)
+        .ok()
+        .and_then(|content| from_str(&content).ok())
+        .unwrap_or_default();
+
+    let mut request = download::agent().get(&Mirror::from_env().rewrite(MANIFEST_URL));
+    if cached.is_some() {
+        if let Some(etag) = &validators.etag {
+            request = request.header("If-None-Match", etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            request = request.header("If-Modified-Since", last_modified);
+        }
+    }
+
+    let content = match (request.call(), cached) {
+        // 304 Not Modified: the cached copy is still current
+        (Ok(response), Some(cached)) if response.status().as_u16() == 304 => cached,
+        (Ok(mut response), _) => {
+            let header = |name: &str| {
+                response
+                    .headers()
+                    .get(name)
+                    .and_then(|value| value.to_str().ok())
+                    .map(String::from)
+            };
+            let validators = CacheValidators {
+                etag: header("ETag"),
+                last_modified: header("Last-Modified"),
+            };
+
+            let content = response
+                .body_mut()
+                .read_to_string()
+                .map_err(|e| io::Error::other(format!("无法下载版本列表: {}", e)))?;
+
+            fs::create_dir_all(mc_path)?;
+            // Parallel installs all refresh the manifest
+            download::write_file_atomic(&cache_path, &content)?;
+            download::write_file_atomic(&validators_path, serde_json::to_string(&validators)?)?;
+
+            content
+        }
+        (Err(e), Some(cached)) => {
+            log::warn!("failed to refresh version manifest ({}), using cached copy", e);
+            cached
+        }
+        (Err(e), None) => {
+            return Err(io::Error::other(format!("无法下载版本列表: {}", e)));
+        }
+    };
+
+    from_str(&content).map_err(|e| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("无效的版本列表: {}", e)))
+}
+
+// Concrete ids are returned as-is without touching the network
+pub fn resolve_version_alias(mc_path: &Path, version: &str) -> io::Result<String> {
+    let resolve = |pick: fn(&LatestVersions) -> &String| -> io::Result<String> {
+        let manifest = fetch_version_manifest(mc_path)?;
+        let resolved = pick(&manifest.latest).clone();
+
+        println!("Resolved {} to {}", version, resolved);
+
+        Ok(resolved)
+    };
+
+    match version {
+        "latest" | "latest-release" => resolve(|latest| &latest.release),
+        "latest-snapshot" => resolve(|latest| &latest.snapshot),
+        _ => Ok(version.to_string()),
+    }
+}
@@ -0,0 +1,64 @@
+use std::{
+    fs,
+    path::Path,
+    process::Command,
+};
+
+use crate::{mods, VersionJson};
+
+// Whatever the machine has, the OS and everything else keep this much
+const RESERVED_MB: u64 = 2048;
+const MIN_HEAP_MB: u64 = 1024;
+
+// A quarter of RAM within 2-4G for vanilla, half within 4-8G for modded instances;
+// more than 8G mostly buys longer GC pauses
+pub fn recommended_memory(total_mb: u64, modded: bool) -> u32 {
+    let (share, floor, ceiling) = if modded { (2, 4096, 8192) } else { (4, 2048, 4096) };
+
+    let heap = (total_mb / share)
+        .clamp(floor, ceiling)
+        .min(total_mb.saturating_sub(RESERVED_MB))
+        .max(MIN_HEAP_MB);
+
+    (heap / 512 * 512) as u32
+}
+
+// A loader in the version JSON, or jars in mods/ for loaders installed some other way
+pub fn is_modded(version_json: &VersionJson, game_dir: &Path) -> bool {
+    if mods::instance_loader(version_json).is_some() || version_json.main_class != "net.minecraft.client.main.Main" {
+        return true;
+    }
+
+    fs::read_dir(game_dir.join("mods")).is_ok_and(|entries| {
+        entries
+            .filter_map(Result::ok)
+            .any(|entry| entry.path().extension().is_some_and(|ext| ext == "jar"))
+    })
+}
+
+pub fn total_memory_mb() -> Option<u64> {
+    if cfg!(windows) {
+        let output = Command::new("powershell")
+            .args(["-NoProfile", "-Command", "(Get-CimInstance Win32_ComputerSystem).TotalPhysicalMemory"])
+            .output()
+            .ok()?;
+        let bytes: u64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+        Some(bytes / 1024 / 1024)
+    } else if cfg!(target_os = "macos") {
+        let output = Command::new("sysctl").args(["-n", "hw.memsize"]).output().ok()?;
+        let bytes: u64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+        Some(bytes / 1024 / 1024)
+    } else {
+        // "MemTotal:       16318412 kB"
+        let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+        let kb: u64 = meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix("MemTotal:"))?
+            .trim()
+            .trim_end_matches("kB")
+            .trim()
+            .parse()
+            .ok()?;
+        Some(kb / 1024)
+    }
+}
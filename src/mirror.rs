@@ -0,0 +1,99 @@
+use std::env;
+
+const MIRROR_ENV: &str = "WMML_MIRROR";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mirror {
+    Official,
+    Bmclapi,
+}
+
+// Official host prefix and its BMCLAPI replacement
+const BMCLAPI_HOSTS: [(&str, &str); 8] = [
+    ("https://piston-meta.mojang.com", "https://bmclapi2.bangbang93.com"),
+    ("https://launchermeta.mojang.com", "https://bmclapi2.bangbang93.com"),
+    ("https://piston-data.mojang.com", "https://bmclapi2.bangbang93.com"),
+    ("https://launcher.mojang.com", "https://bmclapi2.bangbang93.com"),
+    ("https://resources.download.minecraft.net", "https://bmclapi2.bangbang93.com/assets"),
+    ("https://libraries.minecraft.net", "https://bmclapi2.bangbang93.com/maven"),
+    ("https://maven.minecraftforge.net", "https://bmclapi2.bangbang93.com/maven"),
+    ("https://maven.fabricmc.net", "https://bmclapi2.bangbang93.com/maven"),
+];
+
+impl Mirror {
+    // The preferred source for one-off requests such as version metadata
+    pub fn from_env() -> Mirror {
+        Mirror::list_from_env()[0]
+    }
+
+    // WMML_MIRROR accepts "official" (the default) and "bmclapi", or several of them
+    // separated by commas in the order downloads should try them
+    pub fn list_from_env() -> Vec<Mirror> {
+        let Ok(value) = env::var(MIRROR_ENV) else {
+            return vec![Mirror::Official];
+        };
+
+        let mut mirrors = Vec::new();
+        for name in value.split(',').filter(|name| !name.trim().is_empty()) {
+            match Mirror::parse(name) {
+                Some(mirror) if !mirrors.contains(&mirror) => mirrors.push(mirror),
+                Some(_) => {}
+                None => log::warn!("unknown {} value {}, ignoring it", MIRROR_ENV, name.trim()),
+            }
+        }
+
+        if mirrors.is_empty() {
+            if !value.trim().is_empty() {
+                log::warn!("no usable {} value in {}, using the official servers", MIRROR_ENV, value);
+            }
+            mirrors.push(Mirror::Official);
+        }
+
+        mirrors
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mirror::Official => "official",
+            Mirror::Bmclapi => "bmclapi",
+        }
+    }
+
+    // One URL per mirror, skipping mirrors that do not host this URL and would repeat an earlier one
+    pub fn candidates(mirrors: &[Mirror], url: &str) -> Vec<(Mirror, String)> {
+        let mut candidates: Vec<(Mirror, String)> = Vec::new();
+
+        for mirror in mirrors {
+            let rewritten = mirror.rewrite(url);
+            if !candidates.iter().any(|(_, url)| *url == rewritten) {
+                candidates.push((*mirror, rewritten));
+            }
+        }
+
+        candidates
+    }
+
+    fn parse(value: &str) -> Option<Mirror> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "official" | "mojang" => Some(Mirror::Official),
+            "bmclapi" => Some(Mirror::Bmclapi),
+            _ => None,
+        }
+    }
+
+    pub fn rewrite(&self, url: &str) -> String {
+        let hosts: &[(&str, &str)] = match self {
+            Mirror::Official => &[],
+            Mirror::Bmclapi => &BMCLAPI_HOSTS,
+        };
+
+        hosts
+            .iter()
+            .find_map(|(official, mirror)| {
+                url.strip_prefix(official)
+                    .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+                    .map(|rest| format!("{}{}", mirror, rest))
+            })
+            .unwrap_or_else(|| url.to_string())
+    }
+}
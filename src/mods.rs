@@ -0,0 +1,260 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    path::Path,
+};
+use serde::Deserialize;
+use serde_json::from_str;
+use zip::ZipArchive;
+
+use crate::VersionJson;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModLoader {
+    Fabric,
+    Quilt,
+    Forge,
+    NeoForge,
+}
+
+impl ModLoader {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModLoader::Fabric => "fabric",
+            ModLoader::Quilt => "quilt",
+            ModLoader::Forge => "forge",
+            ModLoader::NeoForge => "neoforge",
+        }
+    }
+
+    // Quilt still loads Fabric mods and NeoForge still reads Forge's mods.toml
+    pub fn can_load(&self, mod_loader: ModLoader) -> bool {
+        *self == mod_loader
+            || matches!(
+                (self, mod_loader),
+                (ModLoader::Quilt, ModLoader::Fabric) | (ModLoader::NeoForge, ModLoader::Forge)
+            )
+    }
+}
+
+#[derive(Debug)]
+pub struct ModInfo {
+    pub file_name: String,
+    pub enabled: bool,
+    pub id: Option<String>,
+    pub version: Option<String>,
+    pub name: Option<String>,
+    pub loader: Option<ModLoader>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModMetadata {
+    id: String,
+    version: String,
+    name: Option<String>,
+}
+
+pub fn list_mods(game_dir: &Path) -> io::Result<Vec<ModInfo>> {
+    let mods_dir = game_dir.join("mods");
+    if !mods_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut mods = Vec::new();
+
+    for entry in fs::read_dir(&mods_dir)? {
+        let path = entry?.path();
+        let file_name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+
+        let enabled = if file_name.ends_with(".jar") {
+            true
+        } else if file_name.ends_with(".jar.disabled") {
+            false
+        } else {
+            continue;
+        };
+
+        // Jars without readable metadata are still listed, just without id/version/name
+        let mut archive = File::open(&path).ok().and_then(|file| ZipArchive::new(file).ok());
+        let metadata = archive.as_mut().and_then(|a| read_mod_metadata(a).ok().flatten());
+        let loader = archive.as_ref().and_then(detect_mod_loader);
+
+        mods.push(ModInfo {
+            file_name,
+            enabled,
+            id: metadata.as_ref().map(|m| m.id.clone()),
+            version: metadata.as_ref().map(|m| m.version.clone()),
+            name: metadata.and_then(|m| m.name),
+            loader,
+        });
+    }
+
+    mods.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+
+    Ok(mods)
+}
+
+// The loader a version is installed with, judged by its loader library
+pub fn instance_loader(version_json: &VersionJson) -> Option<ModLoader> {
+    version_json.libraries.iter().find_map(|lib| {
+        let name = lib.name.as_str();
+
+        if name.starts_with("org.quiltmc:quilt-loader:") {
+            Some(ModLoader::Quilt)
+        } else if name.starts_with("net.fabricmc:fabric-loader:") {
+            Some(ModLoader::Fabric)
+        } else if name.starts_with("net.neoforged:") {
+            Some(ModLoader::NeoForge)
+        } else if name.starts_with("net.minecraftforge:forge:") || name.starts_with("net.minecraftforge:fmlloader:") {
+            Some(ModLoader::Forge)
+        } else {
+            None
+        }
+    })
+}
+
+fn detect_mod_loader(archive: &ZipArchive<File>) -> Option<ModLoader> {
+    let has = |name: &str| archive.index_for_name(name).is_some();
+
+    if has("fabric.mod.json") {
+        Some(ModLoader::Fabric)
+    } else if has("quilt.mod.json") {
+        Some(ModLoader::Quilt)
+    } else if has("META-INF/neoforge.mods.toml") {
+        Some(ModLoader::NeoForge)
+    } else if has("META-INF/mods.toml") || has("mcmod.info") {
+        Some(ModLoader::Forge)
+    } else {
+        None
+    }
+}
+
+fn read_mod_metadata(archive: &mut ZipArchive<File>) -> io::Result<Option<ModMetadata>> {
+
+    // Fabric / Quilt (Quilt still ships fabric.mod.json for compatibility)
+    if let Some(content) = read_zip_entry(archive, "fabric.mod.json")? {
+        return Ok(from_str(&content).ok());
+    }
+
+    // Forge / NeoForge
+    for toml_name in ["META-INF/mods.toml", "META-INF/neoforge.mods.toml"] {
+        if let Some(content) = read_zip_entry(archive, toml_name)? {
+            let Some(mut metadata) = parse_mods_toml(&content) else {
+                return Ok(None);
+            };
+
+            // Forge mods usually take their version from the jar manifest
+            if metadata.version == "${file.jarVersion}" {
+                metadata.version = read_zip_entry(archive, "META-INF/MANIFEST.MF")?
+                    .and_then(|manifest| manifest_value(&manifest, "Implementation-Version"))
+                    .unwrap_or_default();
+            }
+
+            return Ok(Some(metadata));
+        }
+    }
+
+    Ok(None)
+}
+
+fn read_zip_entry(archive: &mut ZipArchive<File>, name: &str) -> io::Result<Option<String>> {
+    let mut entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut content = String::new();
+    entry.read_to_string(&mut content)?;
+
+    Ok(Some(content))
+}
+
+// Only the first [[mods]] table is read; that is the jar's primary mod
+fn parse_mods_toml(content: &str) -> Option<ModMetadata> {
+    let mut in_mods = false;
+    let mut id = None;
+    let mut version = None;
+    let mut name = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if line.starts_with('[') {
+            if in_mods && id.is_some() {
+                break;
+            }
+            in_mods = line.split('#').next().unwrap_or_default().trim() == "[[mods]]";
+            continue;
+        }
+
+        if !in_mods {
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        match key.trim() {
+            "modId" => id = parse_toml_string(value),
+            "version" => version = parse_toml_string(value),
+            "displayName" => name = parse_toml_string(value),
+            _ => {}
+        }
+    }
+
+    Some(ModMetadata {
+        id: id?,
+        version: version.unwrap_or_default(),
+        name,
+    })
+}
+
+fn parse_toml_string(value: &str) -> Option<String> {
+    let value = value.trim();
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let rest = &value[1..];
+    let end = rest.find(quote)?;
+
+    Some(rest[..end].to_string())
+}
+
+fn manifest_value(manifest: &str, key: &str) -> Option<String> {
+    manifest.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        (name.trim() == key).then(|| value.trim().to_string())
+    })
+}
+
+pub fn set_mod_enabled(game_dir: &Path, mod_file: &str, enabled: bool) -> io::Result<()> {
+    let mods_dir = game_dir.join("mods");
+    let jar_name = mod_file.strip_suffix(".disabled").unwrap_or(mod_file);
+    let jar_path = mods_dir.join(jar_name);
+    let disabled_path = mods_dir.join(format!("{}.disabled", jar_name));
+
+    let (from, to) = if enabled {
+        (disabled_path, jar_path)
+    } else {
+        (jar_path, disabled_path)
+    };
+
+    if !from.exists() {
+        // Already in the requested state
+        if to.exists() {
+            return Ok(());
+        }
+
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("模组文件不存在: {}", mods_dir.join(jar_name).display())));
+    }
+
+    fs::rename(&from, &to).map_err(|e| io::Error::new(
+        e.kind(),
+        format!("无法重命名 {} 为 {}: {}", from.display(), to.display(), e)))
+}
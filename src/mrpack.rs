@@ -0,0 +1,255 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, Read},
+    path::{Component, Path, PathBuf},
+};
+use serde::Deserialize;
+use serde_json::{from_str, Value};
+use zip::ZipArchive;
+
+use crate::{
+    download, error::WmmlError, install, instance, layout::Layout, platform::TargetPlatform, version_json_path,
+};
+
+const INDEX_FILE_NAME: &str = "modrinth.index.json";
+const FABRIC_PROFILE_URL: &str = "https://meta.fabricmc.net/v2/versions/loader";
+const QUILT_PROFILE_URL: &str = "https://meta.quiltmc.org/v3/versions/loader";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModrinthIndex {
+    format_version: u32,
+    name: String,
+    #[serde(default)]
+    files: Vec<PackFile>,
+    // "minecraft" plus at most one of fabric-loader, quilt-loader, forge and neoforge
+    dependencies: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PackFile {
+    // Relative to the game dir
+    path: String,
+    hashes: HashMap<String, String>,
+    env: Option<PackEnv>,
+    downloads: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PackEnv {
+    // "required", "optional" or "unsupported"
+    client: String,
+}
+
+pub struct InstalledPack {
+    pub name: String,
+    // The version to launch, the loader's when the pack uses one
+    pub version_id: String,
+    pub files: usize,
+}
+
+// Installs the pack's Minecraft and loader versions, downloads its files into the game dir (the
+// one --isolate launches with when isolated) and unpacks overrides/ and client-overrides/ on top
+pub fn install_mrpack(
+    mc_path: &Path,
+    layout: &Layout,
+    pack: &Path,
+    isolate: bool,
+    jobs: usize,
+) -> Result<InstalledPack, WmmlError> {
+    let mut archive = ZipArchive::new(File::open(pack)?).map_err(io::Error::other)?;
+    let index = read_index(&mut archive, pack)?;
+
+    if index.format_version != 1 {
+        log::warn!("{} uses index format {}, only 1 is known", pack.display(), index.format_version);
+    }
+
+    let minecraft = index.dependencies.get("minecraft").ok_or_else(|| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} 没有指定 Minecraft 版本", pack.display())))?;
+
+    // Mods meant for a loader WMML cannot install would otherwise land on vanilla
+    check_loaders(&index.dependencies, pack)?;
+
+    let platform = TargetPlatform::host();
+    install::install_version(mc_path, layout, minecraft, platform, false, true, jobs)?;
+    let version_id = install_loader(mc_path, layout, minecraft, &index.dependencies)?;
+
+    let game_dir = if isolate {
+        instance::prepare_isolated_game_dir(mc_path, layout, &version_id)?
+    } else {
+        mc_path.to_path_buf()
+    };
+
+    let files: Vec<&PackFile> = index
+        .files
+        .iter()
+        .filter(|file| file.env.as_ref().is_none_or(|env| env.client != "unsupported"))
+        .collect();
+    let results = install::parallel_map(&files, jobs, |_, file| download_pack_file(&game_dir, file));
+    let failed = results.iter().filter(|result| result.is_err()).count();
+    for e in results.iter().filter_map(|result| result.as_ref().err()) {
+        log::warn!("{}", e);
+    }
+    if failed > 0 {
+        return Err(io::Error::other(format!("{} 个文件下载失败，重新运行安装以继续", failed)).into());
+    }
+
+    // Client overrides win over the shared ones
+    for prefix in ["overrides/", "client-overrides/"] {
+        extract_overrides(&mut archive, prefix, &game_dir)?;
+    }
+
+    Ok(InstalledPack { name: index.name, version_id, files: files.len() })
+}
+
+fn read_index(archive: &mut ZipArchive<File>, pack: &Path) -> io::Result<ModrinthIndex> {
+    let mut content = String::new();
+    archive
+        .by_name(INDEX_FILE_NAME)
+        .map_err(|e| io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} 中没有 {}: {}", pack.display(), INDEX_FILE_NAME, e)))?
+        .read_to_string(&mut content)?;
+
+    from_str(&content).map_err(|e| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("无效的JSON格式 {}: {}", pack.display(), e)))
+}
+
+fn check_loaders(dependencies: &HashMap<String, String>, pack: &Path) -> io::Result<()> {
+    match dependencies.iter().find(|(name, _)| !["minecraft", "fabric-loader", "quilt-loader"].contains(&name.as_str())) {
+        Some((loader, version)) => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{} 需要 {} {}，无法自动安装；请先运行其安装器，再把整合包的文件放进该版本", pack.display(), loader, version))),
+        None => Ok(()),
+    }
+}
+
+// Fabric and Quilt publish ready version JSONs; check_loaders turns away everything else
+fn install_loader(
+    mc_path: &Path,
+    layout: &Layout,
+    minecraft: &str,
+    dependencies: &HashMap<String, String>,
+) -> io::Result<String> {
+    let (profile_base, loader) = match (dependencies.get("fabric-loader"), dependencies.get("quilt-loader")) {
+        (Some(loader), _) => (FABRIC_PROFILE_URL, loader),
+        (None, Some(loader)) => (QUILT_PROFILE_URL, loader),
+        (None, None) => return Ok(minecraft.to_string()),
+    };
+
+    // Downloaded beside the versions first since the id is only known from the profile itself
+    let url = format!("{}/{}/{}/profile/json", profile_base, minecraft, loader);
+    let staging = mc_path.join(&layout.versions).join(format!("{}-{}.json.download", minecraft, loader));
+    download::download_file(&url, &staging, None)?;
+
+    let content = fs::read_to_string(&staging)?;
+    let profile: Value = from_str(&content).map_err(|e| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("无效的JSON格式 {}: {}", url, e)))?;
+    let id = profile["id"].as_str().map(String::from).ok_or_else(|| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} 中缺少 id", url)))?;
+
+    let json_path = version_json_path(mc_path, layout, &id);
+    if let Some(parent) = json_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(&staging, &json_path)?;
+
+    // Loader libraries only name their maven repository
+    let report = install::resolve_libraries(mc_path, layout, &id, TargetPlatform::host())?;
+    for library in &report.downloadable {
+        println!("Downloading {}", library.path.display());
+        download::download_file(&library.url, &library.path, library.sha1.as_deref())?;
+    }
+
+    Ok(id)
+}
+
+fn download_pack_file(game_dir: &Path, file: &PackFile) -> io::Result<()> {
+    let dest = game_dir.join(safe_relative_path(&file.path)?);
+    let sha1 = file.hashes.get("sha1").map(String::as_str);
+
+    if sha1.is_some_and(|sha1| download::sha1_file(&dest).is_ok_and(|actual| actual.eq_ignore_ascii_case(sha1))) {
+        return Ok(());
+    }
+
+    println!("Downloading {}", file.path);
+    let mut last_error = None;
+    for url in &file.downloads {
+        match download::download_file(url, &dest, sha1) {
+            Ok(()) => return Ok(()),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| io::Error::other(format!("{} 没有下载地址", file.path))))
+}
+
+fn extract_overrides(archive: &mut ZipArchive<File>, prefix: &str, game_dir: &Path) -> io::Result<()> {
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(io::Error::other)?;
+        let name = entry.name().map_err(io::Error::other)?.into_owned();
+        let Some(relative) = name.strip_prefix(prefix).filter(|name| !name.is_empty()) else {
+            continue;
+        };
+
+        let dest = game_dir.join(safe_relative_path(relative)?);
+        if entry.is_dir() {
+            fs::create_dir_all(&dest)?;
+            continue;
+        }
+
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut entry, &mut File::create(&dest)?)?;
+    }
+
+    Ok(())
+}
+
+// Packs come from the internet, so their paths must stay inside the game dir
+fn safe_relative_path(path: &str) -> io::Result<PathBuf> {
+    let path = Path::new(path);
+
+    if path.components().all(|component| matches!(component, Component::Normal(_))) {
+        Ok(path.to_path_buf())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("整合包中的路径不安全: {}", path.display())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    #[test]
+    fn forge_packs_are_refused_before_anything_is_downloaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let pack = dir.path().join("pack.mrpack");
+        let mut writer = ZipWriter::new(File::create(&pack).unwrap());
+        writer.start_file(INDEX_FILE_NAME, SimpleFileOptions::default()).unwrap();
+        writer.write_all(br#"{
+            "formatVersion": 1,
+            "name": "Forge Pack",
+            "files": [{ "path": "mods/a.jar", "hashes": {}, "downloads": ["https://example.invalid/a.jar"] }],
+            "dependencies": { "minecraft": "1.20.1", "forge": "47.2.0" }
+        }"#).unwrap();
+        writer.finish().unwrap();
+        let mc_path = dir.path().join(".minecraft");
+
+        let result = install_mrpack(&mc_path, &Layout::default(), &pack, false, 1);
+
+        assert!(matches!(result, Err(WmmlError::Io(e)) if e.kind() == io::ErrorKind::Unsupported));
+        assert!(!mc_path.exists());
+    }
+}
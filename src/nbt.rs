@@ -0,0 +1,250 @@
+use std::io;
+
+// Deep enough for anything the game writes, shallow enough that a hostile file cannot overflow the stack
+const MAX_DEPTH: usize = 512;
+
+// An uncompressed NBT tag; files such as level.dat are gzipped and have to be inflated first
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Tag>),
+    // Entries keep the order of the file
+    Compound(Vec<(String, Tag)>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(entries) => entries.iter().find(|(key, _)| key == name).map(|(_, tag)| tag),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_byte(&self) -> Option<i8> {
+        match self {
+            Tag::Byte(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(tags) => Some(tags),
+            _ => None,
+        }
+    }
+}
+
+// The root tag of a file, which is always a named compound; the name is usually empty
+pub fn read_root(data: &[u8]) -> io::Result<Tag> {
+    let mut reader = Reader { data, pos: 0 };
+
+    match reader.u8()? {
+        10 => {}
+        0x1f if data.get(1) == Some(&0x8b) => {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "NBT 数据经过 gzip 压缩，需先解压"));
+        }
+        kind => return Err(reader.invalid(format!("根标签类型 {} 不是复合标签", kind))),
+    }
+    reader.string()?;
+
+    reader.payload(10, 0)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn invalid(&self, reason: String) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, format!("无效的 NBT 数据 (偏移 {}): {}", self.pos, reason))
+    }
+
+    fn take(&mut self, length: usize) -> io::Result<&[u8]> {
+        let bytes = self
+            .pos
+            .checked_add(length)
+            .and_then(|end| self.data.get(self.pos..end))
+            .ok_or_else(|| self.invalid("数据意外结束".to_string()))?;
+        self.pos += length;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("take returns N bytes"))
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn length(&mut self) -> io::Result<usize> {
+        let length = i32::from_be_bytes(self.array()?);
+        usize::try_from(length).map_err(|_| self.invalid(format!("负数长度 {}", length)))
+    }
+
+    // Java's modified UTF-8 only differs from UTF-8 for NUL and characters outside the BMP,
+    // which lossy decoding turns into replacement characters
+    fn string(&mut self) -> io::Result<String> {
+        let length = u16::from_be_bytes(self.array()?) as usize;
+        Ok(String::from_utf8_lossy(self.take(length)?).into_owned())
+    }
+
+    fn payload(&mut self, kind: u8, depth: usize) -> io::Result<Tag> {
+        if depth > MAX_DEPTH {
+            return Err(self.invalid("嵌套层数过多".to_string()));
+        }
+
+        Ok(match kind {
+            1 => Tag::Byte(i8::from_be_bytes(self.array()?)),
+            2 => Tag::Short(i16::from_be_bytes(self.array()?)),
+            3 => Tag::Int(i32::from_be_bytes(self.array()?)),
+            4 => Tag::Long(i64::from_be_bytes(self.array()?)),
+            5 => Tag::Float(f32::from_be_bytes(self.array()?)),
+            6 => Tag::Double(f64::from_be_bytes(self.array()?)),
+            7 => {
+                let length = self.length()?;
+                Tag::ByteArray(self.take(length)?.iter().map(|&b| b as i8).collect())
+            }
+            8 => Tag::String(self.string()?),
+            9 => {
+                let element_kind = self.u8()?;
+                let length = self.length()?;
+                // Empty lists may carry the end tag as their element type
+                if element_kind == 0 && length > 0 {
+                    return Err(self.invalid("非空列表的元素类型为空".to_string()));
+                }
+                let mut tags = Vec::new();
+                for _ in 0..length {
+                    tags.push(self.payload(element_kind, depth + 1)?);
+                }
+                Tag::List(tags)
+            }
+            10 => {
+                let mut entries = Vec::new();
+                loop {
+                    let kind = self.u8()?;
+                    if kind == 0 {
+                        break;
+                    }
+                    let name = self.string()?;
+                    entries.push((name, self.payload(kind, depth + 1)?));
+                }
+                Tag::Compound(entries)
+            }
+            11 => {
+                let length = self.length()?;
+                let mut values = Vec::new();
+                for _ in 0..length {
+                    values.push(i32::from_be_bytes(self.array()?));
+                }
+                Tag::IntArray(values)
+            }
+            12 => {
+                let length = self.length()?;
+                let mut values = Vec::new();
+                for _ in 0..length {
+                    values.push(i64::from_be_bytes(self.array()?));
+                }
+                Tag::LongArray(values)
+            }
+            kind => return Err(self.invalid(format!("未知的标签类型 {}", kind))),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(kind: u8, name: &str) -> Vec<u8> {
+        let mut bytes = vec![kind];
+        bytes.extend((name.len() as u16).to_be_bytes());
+        bytes.extend(name.as_bytes());
+        bytes
+    }
+
+    // A root compound { "name": "Server", "count": 3, "ids": [1, 2] }
+    fn sample() -> Vec<u8> {
+        let mut data = named(10, "");
+        data.extend(named(8, "name"));
+        data.extend(6u16.to_be_bytes());
+        data.extend(b"Server");
+        data.extend(named(3, "count"));
+        data.extend(3i32.to_be_bytes());
+        data.extend(named(9, "ids"));
+        data.push(1);
+        data.extend(2i32.to_be_bytes());
+        data.extend([1, 2]);
+        data.push(0);
+        data
+    }
+
+    #[test]
+    fn a_root_compound_is_read() {
+        let root = read_root(&sample()).unwrap();
+        assert_eq!(root.get("name").and_then(Tag::as_str), Some("Server"));
+        assert_eq!(root.get("count"), Some(&Tag::Int(3)));
+        assert_eq!(root.get("ids").and_then(Tag::as_list), Some(&[Tag::Byte(1), Tag::Byte(2)][..]));
+        assert_eq!(root.get("missing"), None);
+    }
+
+    #[test]
+    fn truncated_data_is_an_error() {
+        let data = sample();
+        for length in 0..data.len() {
+            assert!(read_root(&data[..length]).is_err(), "{} bytes", length);
+        }
+    }
+
+    #[test]
+    fn broken_data_is_an_error() {
+        // Gzipped, a root that is not a compound, a negative array length, an unknown tag
+        assert!(read_root(&[0x1f, 0x8b, 8, 0]).is_err());
+        assert!(read_root(&named(8, "")).is_err());
+
+        let mut negative = named(10, "");
+        negative.extend(named(7, "bytes"));
+        negative.extend((-1i32).to_be_bytes());
+        assert!(read_root(&negative).is_err());
+
+        let mut unknown = named(10, "");
+        unknown.extend(named(13, "what"));
+        assert!(read_root(&unknown).is_err());
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error() {
+        // Lists of one list each, far deeper than the game writes
+        let mut lists = named(10, "");
+        lists.extend(named(9, "deep"));
+        for _ in 0..100_000 {
+            lists.push(9);
+            lists.extend(1i32.to_be_bytes());
+        }
+        assert!(read_root(&lists).unwrap_err().to_string().contains("嵌套层数过多"));
+
+        let mut compounds = named(10, "");
+        for _ in 0..100_000 {
+            compounds.extend(named(10, ""));
+        }
+        assert!(read_root(&compounds).unwrap_err().to_string().contains("嵌套层数过多"));
+    }
+}
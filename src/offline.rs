@@ -0,0 +1,81 @@
+use std::{
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+use md5::{Digest, Md5};
+
+// Per-player files of a world save named after the player's UUID; the singleplayer host's
+// inventory lives in level.dat instead and needs no migration
+const PLAYER_DATA_FILES: [(&str, &str); 4] = [
+    ("playerdata", "dat"),
+    ("playerdata", "dat_old"),
+    ("stats", "json"),
+    ("advancements", "json"),
+];
+
+// Same name-based (version 3) UUID the vanilla server gives offline players, so whitelists and saves line up
+pub fn offline_uuid(player_name: &str) -> String {
+    let mut bytes: [u8; 16] = Md5::digest(format!("OfflinePlayer:{}", player_name)).into();
+    bytes[6] = (bytes[6] & 0x0f) | 0x30;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    format_uuid(&bytes)
+}
+
+// Accepts the dashed and the plain 32-digit form, returns the dashed lowercase one
+pub fn parse_uuid(value: &str) -> Option<String> {
+    let value = value.trim();
+    let dashed = value.len() == 36 && [8, 13, 18, 23].iter().all(|&i| value.as_bytes()[i] == b'-');
+    let hex: String = value.chars().filter(|c| *c != '-').collect();
+
+    if (!dashed && value.len() != 32) || hex.len() != 32 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let mut bytes = [0u8; 16];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+
+    Some(format_uuid(&bytes))
+}
+
+// Renames the offline UUID's files to the online one so a player keeps their items, stats and
+// advancements after switching accounts; nothing is renamed if the new UUID already has data
+pub fn migrate_player_data(world_dir: &Path, old_uuid: &str, new_uuid: &str) -> io::Result<Vec<PathBuf>> {
+    if !world_dir.join("level.dat").is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} 不是存档目录", world_dir.display())));
+    }
+
+    let renames: Vec<(PathBuf, PathBuf)> = PLAYER_DATA_FILES
+        .iter()
+        .map(|(dir, extension)| {
+            let dir = world_dir.join(dir);
+            (dir.join(format!("{}.{}", old_uuid, extension)), dir.join(format!("{}.{}", new_uuid, extension)))
+        })
+        .filter(|(from, _)| from.exists())
+        .collect();
+
+    if let Some((_, to)) = renames.iter().find(|(_, to)| to.exists()) {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} 已存在，未迁移任何文件", to.display())));
+    }
+
+    for (from, to) in &renames {
+        fs::rename(from, to).map_err(|e| io::Error::new(
+            e.kind(),
+            format!("无法重命名 {}: {}", from.display(), e)))?;
+    }
+
+    Ok(renames.into_iter().map(|(_, to)| to).collect())
+}
+
+fn format_uuid(bytes: &[u8; 16]) -> String {
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
@@ -0,0 +1,156 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::Command,
+};
+use serde_json::json;
+use zip::ZipArchive;
+
+use crate::{download, layout::Layout, read_version_json, version_json_path};
+
+const LAUNCHWRAPPER_URL: &str =
+    "https://libraries.minecraft.net/net/minecraft/launchwrapper/1.12/launchwrapper-1.12.jar";
+
+// Installs OptiFine as a version inheriting the vanilla one and returns the new version id
+pub fn install_optifine(
+    mc_path: &Path,
+    layout: &Layout,
+    installer: &Path,
+    java_path: &str,
+) -> io::Result<String> {
+    let (mc_version, edition) = parse_installer_name(installer).ok_or_else(|| io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("无法识别的 OptiFine 安装器文件名: {}", installer.display())))?;
+
+    let vanilla_jar = layout
+        .version_dir(mc_path, &mc_version)
+        .join(format!("{}.jar", mc_version));
+    if !vanilla_jar.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("请先安装原版 {}: {}", mc_version, vanilla_jar.display())));
+    }
+    let vanilla_json = read_version_json(&version_json_path(mc_path, layout, &mc_version))?;
+
+    let mut archive = ZipArchive::new(File::open(installer)?)?;
+
+    // The OptiFine library itself; newer installers must patch it against the client jar
+    let lib_version = format!("{}_{}", mc_version, edition);
+    let libraries_dir = layout.libraries_dir(mc_path);
+    let lib_path = library_path(&libraries_dir, "optifine", "OptiFine", &lib_version);
+    fs::create_dir_all(lib_path.parent().unwrap_or(mc_path))?;
+
+    if archive.index_for_name("optifine/Patcher.class").is_some() {
+        println!("Patching OptiFine against {}", vanilla_jar.display());
+
+        let status = Command::new(java_path)
+            .arg("-cp")
+            .arg(installer)
+            .arg("optifine.Patcher")
+            .arg(&vanilla_jar)
+            .arg(installer)
+            .arg(&lib_path)
+            .status()?;
+
+        if !status.success() {
+            return Err(io::Error::other(format!("OptiFine Patcher 运行失败: {}", status)));
+        }
+    } else {
+        fs::copy(installer, &lib_path)?;
+    }
+
+    let launchwrapper = install_launchwrapper(&libraries_dir, &mut archive)?;
+
+    let version_id = format!("{}-OptiFine_{}", mc_version, edition);
+    let mut version_json = json!({
+        "id": version_id,
+        "inheritsFrom": mc_version,
+        "type": "release",
+        "mainClass": "net.minecraft.launchwrapper.Launch",
+        "libraries": [
+            { "name": format!("optifine:OptiFine:{}", lib_version) },
+            { "name": launchwrapper },
+        ],
+    });
+
+    // minecraftArguments replaces the parent's, arguments.game appends to it
+    match &vanilla_json.minecraft_arguments {
+        Some(vanilla_args) => {
+            version_json["minecraftArguments"] =
+                json!(format!("{} --tweakClass optifine.OptiFineTweaker", vanilla_args));
+        }
+        None => {
+            version_json["arguments"] = json!({ "game": ["--tweakClass", "optifine.OptiFineTweaker"] });
+        }
+    }
+
+    let json_path = version_json_path(mc_path, layout, &version_id);
+    fs::create_dir_all(json_path.parent().unwrap_or(mc_path))?;
+    fs::write(&json_path, serde_json::to_string_pretty(&version_json)?)?;
+
+    Ok(version_id)
+}
+
+// OptiFine_1.20.1_HD_U_I6.jar / preview_OptiFine_1.20.1_HD_U_I6_pre1.jar
+fn parse_installer_name(installer: &Path) -> Option<(String, String)> {
+    let stem = installer.file_stem()?.to_str()?;
+    let name = stem.strip_prefix("preview_").unwrap_or(stem);
+    let (mc_version, edition) = name.strip_prefix("OptiFine_")?.split_once("_HD_")?;
+
+    Some((mc_version.to_string(), format!("HD_{}", edition)))
+}
+
+// Returns the library name of the launchwrapper the installer expects
+fn install_launchwrapper(libraries_dir: &Path, archive: &mut ZipArchive<File>) -> io::Result<String> {
+    if archive.index_for_name("launchwrapper-of.txt").is_some() {
+        let mut version = String::new();
+        archive.by_name("launchwrapper-of.txt")?.read_to_string(&mut version)?;
+        let version = version.trim();
+
+        extract_entry(
+            archive,
+            &format!("launchwrapper-of-{}.jar", version),
+            &library_path(libraries_dir, "optifine", "launchwrapper-of", version),
+        )?;
+
+        return Ok(format!("optifine:launchwrapper-of:{}", version));
+    }
+
+    if archive.index_for_name("launchwrapper-2.0.jar").is_some() {
+        extract_entry(
+            archive,
+            "launchwrapper-2.0.jar",
+            &library_path(libraries_dir, "optifine", "launchwrapper", "2.0"),
+        )?;
+
+        return Ok("optifine:launchwrapper:2.0".to_string());
+    }
+
+    // Old installers rely on Mojang's launchwrapper
+    let path = library_path(libraries_dir, "net.minecraft", "launchwrapper", "1.12");
+    if !path.exists() {
+        download::download_file(LAUNCHWRAPPER_URL, &path, None)?;
+    }
+
+    Ok("net.minecraft:launchwrapper:1.12".to_string())
+}
+
+fn library_path(libraries_dir: &Path, group: &str, artifact: &str, version: &str) -> PathBuf {
+    libraries_dir
+        .join(group.replace('.', std::path::MAIN_SEPARATOR_STR))
+        .join(artifact)
+        .join(version)
+        .join(format!("{}-{}.jar", artifact, version))
+}
+
+fn extract_entry(archive: &mut ZipArchive<File>, name: &str, dest: &Path) -> io::Result<()> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut entry = archive.by_name(name)?;
+    io::copy(&mut entry, &mut File::create(dest)?)?;
+
+    Ok(())
+}
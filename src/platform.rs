@@ -0,0 +1,84 @@
+use std::{
+    env,
+    process::Command,
+    sync::OnceLock,
+};
+
+// An OS and arch spelled the way version JSON rules spell them, so a classpath
+// can be built for another machine than the one WMML runs on
+#[derive(Debug, Clone)]
+pub struct TargetPlatform {
+    // "windows", "osx" or "linux"
+    pub os: String,
+    // "x86_64", "x86" or "arm64"
+    pub arch: String,
+    // What Java reports as os.version; unknown for other machines, which fails os.version rules
+    pub os_version: Option<String>,
+}
+
+impl TargetPlatform {
+    pub fn host() -> &'static TargetPlatform {
+        static HOST: OnceLock<TargetPlatform> = OnceLock::new();
+
+        HOST.get_or_init(|| TargetPlatform {
+            os: os_name(env::consts::OS).unwrap_or(env::consts::OS).to_string(),
+            arch: arch_name(env::consts::ARCH).unwrap_or(env::consts::ARCH).to_string(),
+            os_version: host_os_version(),
+        })
+    }
+
+    // "windows", "osx-arm64", "linux-x86"; the arch defaults to x86_64
+    pub fn parse(value: &str) -> Option<TargetPlatform> {
+        let (os, arch) = value.split_once('-').unwrap_or((value, "x86_64"));
+
+        Some(TargetPlatform {
+            os: os_name(os)?.to_string(),
+            arch: arch_name(arch)?.to_string(),
+            os_version: None,
+        })
+    }
+
+    // What ${arch} stands for in natives classifiers
+    pub fn arch_bits(&self) -> &'static str {
+        if self.arch == "x86" { "32" } else { "64" }
+    }
+}
+
+fn os_name(os: &str) -> Option<&'static str> {
+    match os {
+        "windows" => Some("windows"),
+        "osx" | "macos" => Some("osx"),
+        "linux" => Some("linux"),
+        _ => None,
+    }
+}
+
+fn arch_name(arch: &str) -> Option<&'static str> {
+    match arch {
+        "x86_64" | "amd64" => Some("x86_64"),
+        "x86" | "i386" | "i686" => Some("x86"),
+        "arm64" | "aarch64" => Some("arm64"),
+        _ => None,
+    }
+}
+
+// 10.0 on Windows 10/11, the product version on macOS, the kernel on Linux
+fn host_os_version() -> Option<String> {
+    let output = if cfg!(windows) {
+        Command::new("cmd").args(["/C", "ver"]).output()
+    } else if cfg!(target_os = "macos") {
+        Command::new("sw_vers").arg("-productVersion").output()
+    } else {
+        Command::new("uname").arg("-r").output()
+    };
+    let output = String::from_utf8_lossy(&output.ok()?.stdout).trim().to_string();
+
+    if !cfg!(windows) {
+        return Some(output);
+    }
+
+    // "Microsoft Windows [Version 10.0.19045.3803]" -> "10.0"
+    let version = output.rsplit(' ').next()?.trim_end_matches(']');
+    let mut parts = version.split('.');
+    Some(format!("{}.{}", parts.next()?, parts.next()?))
+}
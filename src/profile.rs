@@ -0,0 +1,219 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, to_string_pretty};
+
+use crate::{download, error::WmmlError, offline};
+
+const PROFILE_URL: &str = "https://api.minecraftservices.com/minecraft/profile";
+const SKIN_UPLOAD_URL: &str = "https://api.minecraftservices.com/minecraft/profile/skins";
+const NAME_LOOKUP_URL: &str = "https://api.mojang.com/users/profiles/minecraft";
+// Lowercase player name to premium UUID, kept in the game dir
+const UUID_CACHE_NAME: &str = "wmml-uuid-cache.json";
+// Names can move to another account once their owner renames, so answers expire
+const UUID_CACHE_SECONDS: u64 = 24 * 60 * 60;
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+// The modern skin layout and the one from before 1.8
+const SKIN_SIZES: [(u32, u32); 2] = [(64, 64), (64, 32)];
+
+#[derive(Debug, Deserialize)]
+pub struct PlayerProfile {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub skins: Vec<Skin>,
+    #[serde(default)]
+    pub capes: Vec<Cape>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Skin {
+    pub url: String,
+    pub state: String,
+    // "CLASSIC" or "SLIM"
+    pub variant: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Cape {
+    pub url: String,
+    pub state: String,
+    pub alias: Option<String>,
+}
+
+impl PlayerProfile {
+    pub fn active_skin(&self) -> Option<&Skin> {
+        self.skins.iter().find(|s| s.state == "ACTIVE")
+    }
+
+    pub fn active_cape(&self) -> Option<&Cape> {
+        self.capes.iter().find(|c| c.state == "ACTIVE")
+    }
+}
+
+// The arm width a skin is drawn with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SkinVariant {
+    #[default]
+    Classic,
+    Slim,
+}
+
+impl SkinVariant {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SkinVariant::Classic => "classic",
+            SkinVariant::Slim => "slim",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct NameLookup {
+    id: String,
+}
+
+// The body the services API answers a refused request with
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ServiceError {
+    error_message: Option<String>,
+    developer_message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedUuid {
+    uuid: String,
+    fetched_at: u64,
+}
+
+// The premium UUID for a player name, None when no account has that name; read-only and without
+// auth, for whitelists and the like
+pub fn lookup_uuid(game_dir: &Path, name: &str) -> io::Result<Option<String>> {
+    let cache_path = game_dir.join(UUID_CACHE_NAME);
+    let mut cache: HashMap<String, CachedUuid> = fs::read_to_string(&cache_path)
+        .ok()
+        .and_then(|content| from_str(&content).ok())
+        .unwrap_or_default();
+    let key = name.to_lowercase();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+
+    if let Some(cached) = cache.get(&key).filter(|cached| now.saturating_sub(cached.fetched_at) < UUID_CACHE_SECONDS) {
+        return Ok(Some(cached.uuid.clone()));
+    }
+
+    let content = match download::agent()
+        .get(&format!("{}/{}", NAME_LOOKUP_URL, name))
+        .call()
+        .and_then(|mut response| response.body_mut().read_to_string())
+    {
+        Ok(content) => content,
+        // Older API versions answered 204 with an empty body for unknown names
+        Err(ureq::Error::StatusCode(404 | 204)) => return Ok(None),
+        Err(e) => return Err(io::Error::other(format!("无法查询玩家 {} 的 UUID: {}", name, e))),
+    };
+    if content.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let lookup: NameLookup = from_str(&content).map_err(|e| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("无效的 UUID 查询结果: {}", e)))?;
+    let uuid = offline::parse_uuid(&lookup.id).ok_or_else(|| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("无效的 UUID: {}", lookup.id)))?;
+
+    // A cache that cannot be written only costs another request next time
+    cache.insert(key, CachedUuid { uuid: uuid.clone(), fetched_at: now });
+    if let Err(e) = to_string_pretty(&cache).map_err(io::Error::other).and_then(|content| fs::write(&cache_path, content)) {
+        log::warn!("cannot write {}: {}", cache_path.display(), e);
+    }
+
+    Ok(Some(uuid))
+}
+
+// Offline accounts have no access token and therefore no profile
+pub fn fetch_profile(access_token: Option<&str>) -> io::Result<Option<PlayerProfile>> {
+    let Some(access_token) = access_token else {
+        return Ok(None);
+    };
+
+    let content = download::agent().get(PROFILE_URL)
+        .header("Authorization", &format!("Bearer {}", access_token))
+        .call()
+        .and_then(|mut response| response.body_mut().read_to_string())
+        .map_err(|e| match e {
+            ureq::Error::StatusCode(404) => io::Error::new(
+                io::ErrorKind::NotFound,
+                "该账户没有 Minecraft 档案"),
+            e => io::Error::other(format!("无法获取玩家档案: {}", e)),
+        })?;
+
+    from_str(&content).map(Some).map_err(|e| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("无效的玩家档案: {}", e)))
+}
+
+// Makes the PNG the active skin of the account the token belongs to. The size is checked
+// first, so a file the game cannot use never reaches the API
+pub fn upload_skin(access_token: &str, png_path: &Path, variant: SkinVariant) -> Result<(), WmmlError> {
+    let png = fs::read(png_path)?;
+    let size = png_size(&png).ok_or_else(|| WmmlError::InvalidSkin { path: png_path.to_path_buf(), size: None })?;
+    if !SKIN_SIZES.contains(&size) {
+        return Err(WmmlError::InvalidSkin { path: png_path.to_path_buf(), size: Some(size) });
+    }
+
+    let boundary = format!("----WMMLSkin{:x}", SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0));
+    let mut body = Vec::with_capacity(png.len() + 512);
+    body.extend(format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"variant\"\r\n\r\n{}\r\n\
+         --{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"skin.png\"\r\n\
+         Content-Type: image/png\r\n\r\n",
+        variant.as_str(),
+        b = boundary,
+    ).as_bytes());
+    body.extend(&png);
+    body.extend(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+    // Refusals carry their reason in the body, so statuses are not turned into errors here
+    let mut response = download::agent()
+        .post(SKIN_UPLOAD_URL)
+        .config()
+        .http_status_as_error(false)
+        .build()
+        .header("Authorization", &format!("Bearer {}", access_token))
+        .header("Content-Type", &format!("multipart/form-data; boundary={}", boundary))
+        .send(&body[..])
+        .map_err(|e| io::Error::other(format!("无法上传皮肤: {}", e)))?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+
+    // Proxies and outages answer with HTML, of which only the status is worth showing
+    let content = response.body_mut().read_to_string().unwrap_or_default();
+    let message = from_str::<ServiceError>(&content)
+        .ok()
+        .and_then(|error| error.error_message.or(error.developer_message))
+        .unwrap_or_else(|| status.canonical_reason().unwrap_or("").to_string());
+
+    Err(WmmlError::SkinRejected { status: status.as_u16(), message })
+}
+
+// Width and height from the IHDR chunk, which a PNG must start with
+fn png_size(png: &[u8]) -> Option<(u32, u32)> {
+    if !png.starts_with(PNG_SIGNATURE) || png.get(12..16)? != b"IHDR" {
+        return None;
+    }
+
+    let width = u32::from_be_bytes(png.get(16..20)?.try_into().ok()?);
+    let height = u32::from_be_bytes(png.get(20..24)?.try_into().ok()?);
+
+    Some((width, height))
+}
@@ -0,0 +1,179 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+use serde::Deserialize;
+use serde_json::from_str;
+
+use crate::{download, mirror::Mirror};
+
+// Index of every Mojang Java runtime component per platform
+const RUNTIME_INDEX_URL: &str =
+    "https://piston-meta.mojang.com/v1/products/java-runtime/2ec0cc96c44e5a76b9c8b7c39df7210883d12871/all.json";
+
+#[derive(Debug, Deserialize)]
+struct RuntimeEntry {
+    manifest: RuntimeDownload,
+    version: RuntimeVersion,
+}
+
+#[derive(Debug, Deserialize)]
+struct RuntimeVersion {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RuntimeDownload {
+    url: String,
+    sha1: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RuntimeManifest {
+    files: HashMap<String, RuntimeFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RuntimeFile {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    executable: bool,
+    downloads: Option<RuntimeFileDownloads>,
+    target: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RuntimeFileDownloads {
+    raw: RuntimeDownload,
+}
+
+// Platform keys used by the runtime index
+fn platform() -> &'static str {
+    if cfg!(windows) {
+        if cfg!(target_arch = "x86_64") {
+            "windows-x64"
+        } else if cfg!(target_arch = "aarch64") {
+            "windows-arm64"
+        } else {
+            "windows-x86"
+        }
+    } else if cfg!(target_os = "macos") {
+        if cfg!(target_arch = "aarch64") { "mac-os-arm64" } else { "mac-os" }
+    } else if cfg!(target_arch = "x86") {
+        "linux-i386"
+    } else {
+        "linux"
+    }
+}
+
+fn java_executable(runtime_dir: &Path) -> PathBuf {
+    if cfg!(windows) {
+        runtime_dir.join("bin").join("java.exe")
+    } else if cfg!(target_os = "macos") {
+        runtime_dir.join("jre.bundle").join("Contents").join("Home").join("bin").join("java")
+    } else {
+        runtime_dir.join("bin").join("java")
+    }
+}
+
+// Same layout as the official launcher: runtime/<component>/<platform>/<component>
+pub fn ensure_runtime(mc_path: &Path, component: &str, mut on_progress: impl FnMut(u8)) -> io::Result<PathBuf> {
+    let platform_dir = mc_path.join("runtime").join(component).join(platform());
+    let runtime_dir = platform_dir.join(component);
+    let version_marker = platform_dir.join(format!("{}.version", component));
+    let java = java_executable(&runtime_dir);
+
+    if version_marker.exists() && java.exists() {
+        return Ok(java);
+    }
+
+    let index: HashMap<String, HashMap<String, Vec<RuntimeEntry>>> = fetch_json(RUNTIME_INDEX_URL)?;
+    let entry = index
+        .get(platform())
+        .and_then(|components| components.get(component))
+        .and_then(|entries| entries.first())
+        .ok_or_else(|| io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("没有适用于 {} 的 Java 运行时 {}", platform(), component)))?;
+
+    log::info!("Installing Java runtime {} {}", component, entry.version.name);
+
+    let manifest_path = platform_dir.join(format!("{}.json", component));
+    download::download_file(&entry.manifest.url, &manifest_path, Some(&entry.manifest.sha1))?;
+    let manifest: RuntimeManifest = from_str(&fs::read_to_string(&manifest_path)?).map_err(|e| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("无效的运行时清单 {}: {}", manifest_path.display(), e)))?;
+
+    // Directories first so files and links always have a parent
+    let mut files: Vec<(&String, &RuntimeFile)> = manifest.files.iter().collect();
+    files.sort_by_key(|(path, file)| (file.kind != "directory", path.len()));
+
+    let total = files.len().max(1);
+    for (done, (path, file)) in files.into_iter().enumerate() {
+        let dest = runtime_dir.join(path);
+
+        match (file.kind.as_str(), &file.downloads, &file.target) {
+            ("directory", _, _) => fs::create_dir_all(&dest)?,
+            ("file", Some(downloads), _) => {
+                if !dest.exists() {
+                    download::download_file(&downloads.raw.url, &dest, Some(&downloads.raw.sha1))?;
+                }
+                if file.executable {
+                    set_executable(&dest)?;
+                }
+            }
+            ("link", _, Some(target)) => create_link(target, &dest)?,
+            _ => {}
+        }
+
+        on_progress((done * 100 / total) as u8);
+    }
+
+    fs::write(&version_marker, &entry.version.name)?;
+    on_progress(100);
+
+    Ok(java)
+}
+
+fn fetch_json<T: serde::de::DeserializeOwned>(url: &str) -> io::Result<T> {
+    let content = download::agent().get(&Mirror::from_env().rewrite(url))
+        .call()
+        .and_then(|mut response| response.body_mut().read_to_string())
+        .map_err(|e| io::Error::other(format!("下载失败 {}: {}", url, e)))?;
+
+    from_str(&content).map_err(|e| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("无效的JSON格式 {}: {}", url, e)))
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_mode(permissions.mode() | 0o755);
+    fs::set_permissions(path, permissions)
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+// Only the macOS and Linux runtimes contain links
+#[cfg(unix)]
+fn create_link(target: &str, dest: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(dest).is_ok() {
+        return Ok(());
+    }
+
+    std::os::unix::fs::symlink(target, dest)
+}
+
+#[cfg(not(unix))]
+fn create_link(_target: &str, _dest: &Path) -> io::Result<()> {
+    Ok(())
+}
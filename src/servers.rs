@@ -0,0 +1,119 @@
+use std::{
+    fs,
+    io,
+    path::Path,
+};
+
+use crate::nbt;
+
+// The multiplayer screen's list, in the game dir
+const SERVERS_FILE_NAME: &str = "servers.dat";
+
+#[derive(Debug, Clone)]
+pub struct ServerEntry {
+    pub name: String,
+    // As typed in the game, with or without a port; see srv::resolve_server_address
+    pub address: String,
+}
+
+// Servers in the order the multiplayer screen shows them, empty when the game never saved any
+pub fn list_servers(game_dir: &Path) -> io::Result<Vec<ServerEntry>> {
+    let path = game_dir.join(SERVERS_FILE_NAME);
+    let data = match fs::read(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let root = nbt::read_root(&data)
+        .map_err(|e| io::Error::new(e.kind(), format!("无法读取 {}: {}", path.display(), e)))?;
+    let Some(servers) = root.get("servers").and_then(|servers| servers.as_list()) else {
+        return Ok(Vec::new());
+    };
+
+    Ok(servers
+        .iter()
+        // Hidden entries are the game remembering the last direct connection
+        .filter(|server| server.get("hidden").and_then(|hidden| hidden.as_byte()).unwrap_or(0) == 0)
+        .filter_map(|server| {
+            let address = server.get("ip")?.as_str()?.trim();
+            if address.is_empty() {
+                return None;
+            }
+
+            Some(ServerEntry {
+                name: server.get("name").and_then(|name| name.as_str()).unwrap_or("").to_string(),
+                address: address.to_string(),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(kind: u8, name: &str) -> Vec<u8> {
+        let mut bytes = vec![kind];
+        bytes.extend((name.len() as u16).to_be_bytes());
+        bytes.extend(name.as_bytes());
+        bytes
+    }
+
+    fn string(name: &str, value: &str) -> Vec<u8> {
+        let mut bytes = named(8, name);
+        bytes.extend((value.len() as u16).to_be_bytes());
+        bytes.extend(value.as_bytes());
+        bytes
+    }
+
+    // servers.dat as the game writes it, one compound per entry
+    fn servers_dat(servers: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = named(10, "");
+        data.extend(named(9, "servers"));
+        data.push(if servers.is_empty() { 0 } else { 10 });
+        data.extend((servers.len() as i32).to_be_bytes());
+        for server in servers {
+            data.extend(server);
+            data.push(0);
+        }
+        data.push(0);
+        data
+    }
+
+    #[test]
+    fn visible_servers_are_listed_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut hidden = string("ip", "127.0.0.1:25570");
+        hidden.extend(named(1, "hidden"));
+        hidden.push(1);
+        let mut shown = string("name", "Survival");
+        shown.extend(string("ip", " play.example.com "));
+        shown.extend(named(1, "hidden"));
+        shown.push(0);
+        let servers = [hidden, shown, string("ip", "mc.example.org:25566"), string("name", "No address")];
+        fs::write(dir.path().join(SERVERS_FILE_NAME), servers_dat(&servers)).unwrap();
+
+        let listed = list_servers(dir.path()).unwrap();
+        let listed: Vec<_> = listed.iter().map(|server| (server.name.as_str(), server.address.as_str())).collect();
+        assert_eq!(listed, [("Survival", "play.example.com"), ("", "mc.example.org:25566")]);
+    }
+
+    #[test]
+    fn empty_and_missing_lists_have_no_servers() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(list_servers(dir.path()).unwrap().is_empty());
+
+        fs::write(dir.path().join(SERVERS_FILE_NAME), servers_dat(&[])).unwrap();
+        assert!(list_servers(dir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn a_broken_file_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = servers_dat(&[string("ip", "play.example.com")]);
+        fs::write(dir.path().join(SERVERS_FILE_NAME), &data[..data.len() - 3]).unwrap();
+
+        assert!(list_servers(dir.path()).is_err());
+    }
+}
@@ -0,0 +1,145 @@
+use std::{
+    fmt,
+    fs,
+    io,
+    path::{Path, PathBuf},
+    process::{Child, ExitStatus},
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::interrupt::{self, InterruptPolicy};
+
+// Only the end of latest.log tells how the game went down
+const LOG_TAIL_LINES: usize = 100;
+// Logged by the client when it shuts down on its own, from the menu or by closing the window
+const STOP_MARKER: &str = "Stopping!";
+// Logged after the crash report, followed by where it was saved
+const CRASH_MARKER: &str = "#@!@# Game crashed!";
+
+// How a session WMML waited for ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionOutcome {
+    CleanExit,
+    // The report under crash-reports/ when the game wrote one
+    Crash { report: Option<PathBuf> },
+    // Stopped on Ctrl-C
+    KilledByUser,
+    // Stopped for running past --timeout
+    Timeout,
+}
+
+impl fmt::Display for SessionOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionOutcome::CleanExit => write!(f, "closed normally"),
+            SessionOutcome::Crash { report: Some(report) } => write!(f, "crashed, see {}", report.display()),
+            SessionOutcome::Crash { report: None } => write!(f, "crashed"),
+            SessionOutcome::KilledByUser => write!(f, "was stopped on request"),
+            SessionOutcome::Timeout => write!(f, "was stopped after running past the timeout"),
+        }
+    }
+}
+
+// Does what Ctrl-C and --timeout ask for while a launch waits, remembering whether WMML
+// stopped the game itself so that exit is not taken for a crash
+pub struct SessionWatch {
+    pub launched_at: SystemTime,
+    started: Instant,
+    timeout: Option<Duration>,
+    on_interrupt: InterruptPolicy,
+    stopped: Option<SessionOutcome>,
+}
+
+impl SessionWatch {
+    pub fn start(on_interrupt: InterruptPolicy, timeout: Option<Duration>) -> SessionWatch {
+        SessionWatch {
+            launched_at: SystemTime::now(),
+            started: Instant::now(),
+            timeout,
+            on_interrupt,
+            stopped: None,
+        }
+    }
+
+    // Called between polls of the running game; true when it is to be left running
+    pub fn check(&mut self, child: &mut Child) -> io::Result<bool> {
+        if self.stopped.is_none() && self.timeout.is_some_and(|timeout| self.started.elapsed() >= timeout) {
+            println!("Minecraft ran past the timeout, stopping it");
+            interrupt::stop_game(child)?;
+            self.stopped = Some(SessionOutcome::Timeout);
+        }
+
+        if interrupt::take() {
+            if interrupt::handle(child, self.on_interrupt)? {
+                return Ok(true);
+            }
+            self.stopped.get_or_insert(SessionOutcome::KilledByUser);
+        }
+
+        Ok(false)
+    }
+
+    pub fn outcome(self, game_dir: &Path, status: ExitStatus) -> SessionOutcome {
+        match self.stopped {
+            Some(outcome) => outcome,
+            None => classify_exit(game_dir, self.launched_at, status),
+        }
+    }
+}
+
+// A crash report from this session decides; otherwise the log's last stop marker has to come
+// after its last exception, unless the exit code already says all went well
+fn classify_exit(game_dir: &Path, launched_at: SystemTime, status: ExitStatus) -> SessionOutcome {
+    if let Some(report) = newest_crash_report(game_dir, launched_at) {
+        return SessionOutcome::Crash { report: Some(report) };
+    }
+
+    let log_path = game_dir.join("logs").join("latest.log");
+    let log = fs::metadata(&log_path)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| modified >= launched_at)
+        .then(|| fs::read(&log_path).ok())
+        .flatten()
+        .map(|log| String::from_utf8_lossy(&log).into_owned())
+        .unwrap_or_default();
+    let lines: Vec<&str> = log.lines().collect();
+    let tail = &lines[lines.len().saturating_sub(LOG_TAIL_LINES)..];
+
+    if let Some(line) = tail.iter().rfind(|line| line.contains(CRASH_MARKER)) {
+        // "#@!@# Game crashed! Crash report saved to: #@!@# <path>"
+        let report = line.rsplit("#@!@#").next().map(str::trim).filter(|path| !path.is_empty()).map(PathBuf::from);
+        return SessionOutcome::Crash { report };
+    }
+
+    let stop = tail.iter().rposition(|line| line.contains(STOP_MARKER));
+    let exception = tail.iter().rposition(|line| is_exception_line(line));
+
+    match (stop, exception) {
+        (Some(stop), exception) if status.success() || exception.is_none_or(|exception| exception < stop) => {
+            SessionOutcome::CleanExit
+        }
+        (_, Some(_)) => SessionOutcome::Crash { report: None },
+        _ if status.success() => SessionOutcome::CleanExit,
+        _ => SessionOutcome::Crash { report: None },
+    }
+}
+
+fn is_exception_line(line: &str) -> bool {
+    let line = line.trim_start();
+
+    line.starts_with("at ")
+        || line.starts_with("Caused by: ")
+        || line.contains("Exception in thread")
+        || line.contains("Exception: ")
+}
+
+fn newest_crash_report(game_dir: &Path, launched_at: SystemTime) -> Option<PathBuf> {
+    fs::read_dir(game_dir.join("crash-reports"))
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|extension| extension == "txt"))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .filter(|(modified, _)| *modified >= launched_at)
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
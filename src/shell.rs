@@ -0,0 +1,62 @@
+// Quotes one argument so the shell of the current platform passes it through unchanged
+pub fn quote(arg: &str) -> String {
+    if cfg!(windows) {
+        quote_cmd(arg)
+    } else {
+        quote_sh(arg)
+    }
+}
+
+// Sets a variable for the command that follows it on the same line
+pub fn env_prefix(key: &str, value: &str) -> String {
+    if cfg!(windows) {
+        format!("set \"{}={}\" &&", key, value)
+    } else {
+        format!("{}={}", key, quote_sh(value))
+    }
+}
+
+fn quote_sh(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg.chars().all(|c| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c));
+
+    if safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+// Follows the CommandLineToArgvW rules java.exe parses its arguments with
+fn quote_cmd(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && !arg.contains(|c: char| c.is_whitespace() || "\"&|<>()^".contains(c));
+
+    if safe {
+        return arg.to_string();
+    }
+
+    let mut quoted = String::from("\"");
+    let mut backslashes = 0;
+
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                // Backslashes are only special right before a quote
+                quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,335 @@
+use std::{cmp::Reverse, io, sync::mpsc, thread, time::Duration};
+
+// Minecraft servers announce their real host and port in this SRV record of their name
+const SRV_PREFIX: &str = "_minecraft._tcp.";
+
+// The lookup is only reported, so a slow or unreachable DNS server must not hold up the launch
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(1);
+
+struct SrvRecord {
+    priority: u16,
+    weight: u16,
+    port: u16,
+    target: String,
+}
+
+// Where a server address typed as in the multiplayer screen really points, or None when it names
+// its port or an IP itself or has no SRV record; the game then connects to port 25565 of the name
+pub fn resolve_server_address(address: &str) -> io::Result<Option<(String, u16)>> {
+    let host = address.trim();
+    if host.is_empty() || host.contains(':') || host.parse::<std::net::IpAddr>().is_ok() {
+        return Ok(None);
+    }
+
+    // A query still waiting after the timeout is left to finish on its own thread
+    let name = format!("{}{}", SRV_PREFIX, host.trim_end_matches('.'));
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(query_srv(&name));
+    });
+    let records = receiver
+        .recv_timeout(LOOKUP_TIMEOUT)
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "DNS 查询超时"))??;
+
+    // Lowest priority first, then the heaviest; a target of "." means the service is not offered
+    Ok(records
+        .into_iter()
+        .filter(|record| !record.target.is_empty() && record.target != ".")
+        .min_by_key(|record| (record.priority, Reverse(record.weight)))
+        .map(|record| (record.target.trim_end_matches('.').to_string(), record.port)))
+}
+
+#[cfg(unix)]
+fn query_srv(name: &str) -> io::Result<Vec<SrvRecord>> {
+    use std::{
+        fs,
+        net::{IpAddr, SocketAddr, UdpSocket},
+        process,
+    };
+
+    let resolv_conf = fs::read_to_string("/etc/resolv.conf").unwrap_or_default();
+    let nameservers: Vec<IpAddr> = resolv_conf
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        .filter_map(|address| address.trim().parse().ok())
+        .collect();
+    if nameservers.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "/etc/resolv.conf 中没有 DNS 服务器"));
+    }
+
+    // A connected socket only hears from the nameserver; the id also rejects late answers to
+    // an earlier query
+    let id = process::id() as u16;
+    let query = build_query(id, name);
+    let mut last_error = None;
+
+    for nameserver in nameservers {
+        let bind: SocketAddr = if nameserver.is_ipv4() { ([0, 0, 0, 0], 0).into() } else { ([0u16; 8], 0).into() };
+        let answer = UdpSocket::bind(bind).and_then(|socket| {
+            socket.set_read_timeout(Some(LOOKUP_TIMEOUT))?;
+            socket.connect((nameserver, 53))?;
+            socket.send(&query)?;
+
+            let mut buf = [0; 4096];
+            let read = socket.recv(&mut buf)?;
+            parse_response(&buf[..read], id)
+        });
+
+        match answer {
+            Ok(records) => return Ok(records),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| io::Error::other("DNS 查询失败")))
+}
+
+#[cfg(unix)]
+fn build_query(id: u16, name: &str) -> Vec<u8> {
+    let mut query = Vec::with_capacity(name.len() + 18);
+    query.extend(id.to_be_bytes());
+    // Recursion desired, one question
+    query.extend([0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in name.split('.').filter(|label| !label.is_empty()) {
+        query.push(label.len().min(63) as u8);
+        query.extend(&label.as_bytes()[..label.len().min(63)]);
+    }
+    // Root label, type SRV, class IN
+    query.extend([0, 0, 33, 0, 1]);
+
+    query
+}
+
+#[cfg(unix)]
+fn parse_response(message: &[u8], id: u16) -> io::Result<Vec<SrvRecord>> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "无效的 DNS 响应");
+    let u16_at = |pos: usize| -> io::Result<u16> {
+        message.get(pos..pos + 2).map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]])).ok_or_else(invalid)
+    };
+
+    if u16_at(0)? != id {
+        return Err(invalid());
+    }
+    match u16_at(2)? & 0x0f {
+        0 => {}
+        // NXDOMAIN: the name has no records at all
+        3 => return Ok(Vec::new()),
+        rcode => return Err(io::Error::other(format!("DNS 服务器返回错误 {}", rcode))),
+    }
+
+    let questions = u16_at(4)?;
+    let answers = u16_at(6)?;
+    let mut pos = 12;
+
+    for _ in 0..questions {
+        pos = read_name(message, pos).ok_or_else(invalid)?.1 + 4;
+    }
+
+    let mut records = Vec::new();
+    for _ in 0..answers {
+        pos = read_name(message, pos).ok_or_else(invalid)?.1;
+        let kind = u16_at(pos)?;
+        let data_length = u16_at(pos + 8)? as usize;
+        let data = pos + 10;
+
+        if kind == 33 {
+            records.push(SrvRecord {
+                priority: u16_at(data)?,
+                weight: u16_at(data + 2)?,
+                port: u16_at(data + 4)?,
+                target: read_name(message, data + 6).ok_or_else(invalid)?.0,
+            });
+        }
+        pos = data + data_length;
+    }
+
+    Ok(records)
+}
+
+// A possibly compressed name and the position after it
+#[cfg(unix)]
+fn read_name(message: &[u8], mut pos: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    let mut jumps = 0;
+
+    loop {
+        let length = *message.get(pos)? as usize;
+        if length == 0 {
+            pos += 1;
+            break;
+        }
+
+        if length & 0xc0 == 0xc0 {
+            // Pointers may chain, but a loop of them is a broken message
+            jumps += 1;
+            if jumps > 16 {
+                return None;
+            }
+            end.get_or_insert(pos + 2);
+            pos = ((length & 0x3f) << 8) | *message.get(pos + 1)? as usize;
+            continue;
+        }
+
+        labels.push(String::from_utf8_lossy(message.get(pos + 1..pos + 1 + length)?).into_owned());
+        pos += 1 + length;
+    }
+
+    Some((labels.join("."), end.unwrap_or(pos)))
+}
+
+#[cfg(windows)]
+fn query_srv(name: &str) -> io::Result<Vec<SrvRecord>> {
+    use std::{ffi::c_void, ptr};
+
+    // DNS_RECORDW with the DNS_SRV_DATAW member of its data union
+    #[repr(C)]
+    struct DnsRecord {
+        next: *mut DnsRecord,
+        name: *const u16,
+        kind: u16,
+        data_length: u16,
+        flags: u32,
+        ttl: u32,
+        reserved: u32,
+        target: *const u16,
+        priority: u16,
+        weight: u16,
+        port: u16,
+        pad: u16,
+    }
+
+    #[link(name = "dnsapi")]
+    extern "system" {
+        fn DnsQuery_W(
+            name: *const u16,
+            kind: u16,
+            options: u32,
+            extra: *mut c_void,
+            results: *mut *mut DnsRecord,
+            reserved: *mut c_void,
+        ) -> i32;
+        fn DnsRecordListFree(records: *mut DnsRecord, free_type: i32);
+    }
+
+    const DNS_TYPE_SRV: u16 = 33;
+    const DNS_FREE_RECORD_LIST: i32 = 1;
+    const DNS_ERROR_RCODE_NAME_ERROR: i32 = 9003;
+    const DNS_INFO_NO_RECORDS: i32 = 9501;
+
+    let wide_name: Vec<u16> = name.encode_utf16().chain([0]).collect();
+    let mut results = ptr::null_mut();
+
+    // SAFETY: the name is NUL terminated and results receives a list freed below
+    let status = unsafe {
+        DnsQuery_W(wide_name.as_ptr(), DNS_TYPE_SRV, 0, ptr::null_mut(), &mut results, ptr::null_mut())
+    };
+    match status {
+        0 => {}
+        DNS_ERROR_RCODE_NAME_ERROR | DNS_INFO_NO_RECORDS => return Ok(Vec::new()),
+        code => return Err(io::Error::from_raw_os_error(code)),
+    }
+
+    let mut records = Vec::new();
+    let mut record = results;
+    while !record.is_null() {
+        // SAFETY: every record in the list stays valid until DnsRecordListFree
+        let current = unsafe { &*record };
+        if current.kind == DNS_TYPE_SRV && !current.target.is_null() {
+            // SAFETY: the target is a NUL terminated wide string owned by the record
+            let target = unsafe {
+                let length = (0..).take_while(|&i| *current.target.add(i) != 0).count();
+                String::from_utf16_lossy(std::slice::from_raw_parts(current.target, length))
+            };
+            records.push(SrvRecord {
+                priority: current.priority,
+                weight: current.weight,
+                port: current.port,
+                target,
+            });
+        }
+        record = current.next;
+    }
+
+    // SAFETY: results came from DnsQuery_W and is not used afterwards
+    unsafe { DnsRecordListFree(results, DNS_FREE_RECORD_LIST) };
+
+    Ok(records)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn query_srv(_name: &str) -> io::Result<Vec<SrvRecord>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "此平台不支持 SRV 查询"))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    // A response to build_query(7, "_minecraft._tcp.example.com") with the given answers
+    fn response(answers: &[&[u8]]) -> Vec<u8> {
+        let mut message = build_query(7, "_minecraft._tcp.example.com");
+        message[2] = 0x81;
+        message[3] = 0x80;
+        message[7] = answers.len() as u8;
+        for answer in answers {
+            message.extend(*answer);
+        }
+        message
+    }
+
+    // An SRV answer named by a pointer to the question, its target partly compressed as well
+    fn srv_answer(priority: u8, weight: u8, port: u16, target: &[u8]) -> Vec<u8> {
+        let mut answer = vec![0xc0, 12, 0, 33, 0, 1, 0, 0, 0, 60, 0, 6 + target.len() as u8];
+        answer.extend([0, priority, 0, weight]);
+        answer.extend(port.to_be_bytes());
+        answer.extend(target);
+        answer
+    }
+
+    #[test]
+    fn compressed_names_are_followed() {
+        // "mc" followed by a pointer to "example.com" inside the question at offset 28
+        let first = srv_answer(10, 5, 25566, &[2, b'm', b'c', 0xc0, 28]);
+        let second = srv_answer(20, 0, 25567, &[7, b'b', b'a', b'c', b'k', b'u', b'p', b's', 0]);
+        let message = response(&[&first, &second]);
+
+        let records = parse_response(&message, 7).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!((records[0].priority, records[0].weight, records[0].port), (10, 5, 25566));
+        assert_eq!(records[0].target, "mc.example.com");
+        assert_eq!(records[1].target, "backups");
+
+        assert_eq!(read_name(&message, 12), Some(("_minecraft._tcp.example.com".to_string(), 41)));
+        let pointer = message.len() - second.len();
+        assert_eq!(read_name(&message, pointer), Some(("_minecraft._tcp.example.com".to_string(), pointer + 2)));
+    }
+
+    #[test]
+    fn broken_responses_are_errors() {
+        let answer = srv_answer(0, 0, 25565, &[2, b'm', b'c', 0]);
+        let message = response(&[&answer]);
+
+        assert!(parse_response(&message, 8).is_err());
+        for length in 0..message.len() {
+            assert!(parse_response(&message[..length], 7).is_err(), "{} bytes", length);
+        }
+
+        // Pointers that loop or lead past the end
+        assert_eq!(read_name(&[0xc0, 0], 0), None);
+        assert_eq!(read_name(&[1, b'a', 0xc0, 0], 0), None);
+        assert_eq!(read_name(&[0xc0, 9], 0), None);
+        assert_eq!(read_name(&[0xc0], 0), None);
+        assert_eq!(read_name(&[5, b'a'], 0), None);
+    }
+
+    #[test]
+    fn a_missing_name_has_no_records() {
+        let mut message = response(&[]);
+        message[3] = 0x83;
+        assert!(parse_response(&message, 7).unwrap().is_empty());
+
+        message[3] = 0x82;
+        assert!(parse_response(&message, 7).is_err());
+    }
+}
@@ -0,0 +1,103 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+    process::{Child, ExitStatus},
+    sync::OnceLock,
+    thread,
+    time::{Duration, SystemTime},
+};
+use regex::Regex;
+
+use crate::session::SessionWatch;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// Prints what the game writes to its log until it exits, or returns None when Ctrl-C left it
+// running. The game moves the previous latest.log aside at startup, so a file older than the
+// launch is skipped
+pub fn follow_log(child: &mut Child, log_path: &Path, watch: &mut SessionWatch) -> io::Result<Option<ExitStatus>> {
+    let mut log = LogFollower { position: 0, partial: Vec::new() };
+
+    loop {
+        let status = child.try_wait()?;
+        log.read_new_lines(log_path, watch.launched_at)?;
+
+        if let Some(status) = status {
+            log.flush_partial();
+            return Ok(Some(status));
+        }
+
+        if watch.check(child)? {
+            log.flush_partial();
+            return Ok(None);
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+struct LogFollower {
+    position: u64,
+    partial: Vec<u8>,
+}
+
+impl LogFollower {
+    fn read_new_lines(&mut self, log_path: &Path, launched_at: SystemTime) -> io::Result<()> {
+        let mut file = match File::open(log_path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        let metadata = file.metadata()?;
+        if metadata.modified().is_ok_and(|modified| modified < launched_at) {
+            return Ok(());
+        }
+
+        // A shorter file means the game started a new one
+        if metadata.len() < self.position {
+            self.position = 0;
+            self.partial.clear();
+        }
+
+        file.seek(SeekFrom::Start(self.position))?;
+        let mut content = Vec::new();
+        self.position += file.read_to_end(&mut content)? as u64;
+
+        self.partial.extend_from_slice(&content);
+        while let Some(end) = self.partial.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.partial.drain(..=end).collect();
+            print_log_line(String::from_utf8_lossy(&line).trim_end());
+        }
+
+        Ok(())
+    }
+
+    fn flush_partial(&mut self) {
+        if !self.partial.is_empty() {
+            print_log_line(String::from_utf8_lossy(&self.partial).trim_end());
+            self.partial.clear();
+        }
+    }
+}
+
+// "[12:34:56] [Render thread/INFO]: message", Forge adds "[logger/]" before the colon;
+// anything else, such as stack trace lines, is printed as is
+fn print_log_line(line: &str) {
+    static LOG_LINE: OnceLock<Regex> = OnceLock::new();
+    let pattern = LOG_LINE.get_or_init(|| {
+        Regex::new(r"^\[(\d{2}:\d{2}:\d{2})\] \[([^\]]*)/(\w+)\](?: \[[^\]]*\])?: (.*)$").unwrap()
+    });
+
+    let Some(captures) = pattern.captures(line) else {
+        println!("{}", line);
+        return;
+    };
+
+    let (time, thread, level, message) = (&captures[1], &captures[2], &captures[3], &captures[4]);
+    match level {
+        "WARN" | "ERROR" | "FATAL" => eprintln!("{} {:<5} [{}] {}", time, level, thread, message),
+        _ => println!("{} {:<5} [{}] {}", time, level, thread, message),
+    }
+}
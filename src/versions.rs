@@ -0,0 +1,258 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+use chrono::{DateTime, FixedOffset};
+use serde_json::Value;
+
+use crate::{
+    client_jar_path, java, layout::Layout, read_version, resolve_version, version_json_path, GameArgument,
+    LaunchOptions, VersionJson,
+};
+
+// Placeholders vanilla versions use; WMML leaves out the few it cannot fill
+const GAME_PLACEHOLDERS: [&str; 20] = [
+    "auth_player_name", "version_name", "game_directory", "assets_root", "assets_index_name", "game_assets",
+    "auth_uuid", "auth_access_token", "auth_session", "user_type", "version_type", "user_properties",
+    "quickPlaySingleplayer", "quickPlayMultiplayer", "quickPlayRealms", "quickPlayPath",
+    "resolution_width", "resolution_height", "clientid", "auth_xuid",
+];
+const JVM_PLACEHOLDERS: [&str; 7] = [
+    "natives_directory", "library_directory", "classpath_separator", "launcher_name", "launcher_version",
+    "version_name", "classpath",
+];
+
+#[derive(Debug)]
+pub struct VersionInfo {
+    pub id: String,
+    pub version_type: Option<String>,
+    pub release_time: Option<DateTime<FixedOffset>>,
+    pub time: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Debug)]
+pub struct BrokenVersion {
+    pub id: String,
+    pub problems: Vec<VersionProblem>,
+}
+
+#[derive(Debug)]
+pub enum VersionProblem {
+    // The JSON of this version or one it inherits from
+    InvalidJson(String),
+    MissingParent(String),
+    MissingClientJar(PathBuf),
+    // found is None when the Java could not be run at all
+    NoCompatibleJava { java: String, required: u32, found: Option<u32> },
+}
+
+impl fmt::Display for VersionProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VersionProblem::InvalidJson(e) => write!(f, "unreadable version JSON: {}", e),
+            VersionProblem::MissingParent(id) => write!(f, "inherits from {} which is not installed", id),
+            VersionProblem::MissingClientJar(path) => write!(f, "client jar {} is missing", path.display()),
+            VersionProblem::NoCompatibleJava { java, required, found: Some(found) } => {
+                write!(f, "needs Java {} but {} is Java {}", required, java, found)
+            }
+            VersionProblem::NoCompatibleJava { java, required, found: None } => {
+                write!(f, "needs Java {} but {} cannot be run", required, java)
+            }
+        }
+    }
+}
+
+// Mistakes a hand-edited JSON can carry that only show up as odd failures at launch; the JSON
+// alone is looked at, so inherited fields count as missing unless inheritsFrom is set
+pub fn lint_version_json(version_json: &VersionJson) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let inherits = version_json.inherits_from.is_some();
+
+    if version_json.main_class.trim().is_empty() {
+        warnings.push("mainClass is empty".to_string());
+    }
+
+    if version_json.libraries.is_empty() && !inherits {
+        warnings.push("libraries is empty and nothing is inherited".to_string());
+    }
+
+    match (&version_json.arguments, &version_json.minecraft_arguments) {
+        (Some(_), Some(_)) => warnings.push(
+            "both arguments and minecraftArguments are set, their game arguments are passed twice".to_string()),
+        (None, None) if !inherits => warnings.push("neither arguments nor minecraftArguments is set".to_string()),
+        _ => {}
+    }
+
+    let mut game_args: Vec<String> = version_json
+        .minecraft_arguments
+        .iter()
+        .flat_map(|args| args.split_whitespace().map(String::from))
+        .collect();
+    let mut jvm_args = Vec::new();
+    if let Some(arguments) = &version_json.arguments {
+        game_args.extend(arguments.game.iter().flat_map(argument_strings));
+        jvm_args.extend(arguments.jvm.iter().flat_map(argument_strings));
+    }
+
+    for (args, known, kind) in [(&game_args, &GAME_PLACEHOLDERS[..], "game"), (&jvm_args, &JVM_PLACEHOLDERS[..], "JVM")] {
+        let mut reported = Vec::new();
+        for placeholder in args.iter().flat_map(|arg| placeholders(arg)) {
+            if !known.contains(&placeholder) && !reported.contains(&placeholder) {
+                warnings.push(format!("unknown {} argument placeholder ${{{}}}", kind, placeholder));
+                reported.push(placeholder);
+            }
+        }
+    }
+
+    warnings
+}
+
+// Every value an argument entry can expand to, whatever its rules say
+fn argument_strings(argument: &GameArgument) -> Vec<String> {
+    match argument {
+        GameArgument::String(s) => vec![s.clone()],
+        GameArgument::Object(object) => match object.get("value") {
+            Some(Value::String(s)) => vec![s.clone()],
+            Some(Value::Array(values)) => values.iter().filter_map(|v| v.as_str().map(String::from)).collect(),
+            _ => Vec::new(),
+        },
+    }
+}
+
+fn placeholders(arg: &str) -> Vec<&str> {
+    let mut found = Vec::new();
+    let mut rest = arg;
+
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        found.push(&rest[start + 2..start + end]);
+        rest = &rest[start + end + 1..];
+    }
+
+    found
+}
+
+// Newest release first; versions without a date go last, by id
+pub fn list_versions(mc_path: &Path, layout: &Layout) -> io::Result<Vec<VersionInfo>> {
+    let mut versions = Vec::new();
+
+    for id in installed_version_ids(mc_path, layout)? {
+        // Loader versions inherit their dates from the game version they extend
+        let Ok(version_json) = resolve_version(mc_path, layout, &id) else {
+            continue;
+        };
+
+        versions.push(VersionInfo {
+            id,
+            version_type: version_json.version_type,
+            release_time: version_json.release_time,
+            time: version_json.time,
+        });
+    }
+
+    versions.sort_by(|a, b| b.release_time.cmp(&a.release_time).then_with(|| a.id.cmp(&b.id)));
+
+    Ok(versions)
+}
+
+// Every installed version that cannot launch, with all the reasons found
+pub fn find_broken_versions(mc_path: &Path, layout: &Layout, options: &LaunchOptions) -> io::Result<Vec<BrokenVersion>> {
+    let mut java_majors: HashMap<String, Option<u32>> = HashMap::new();
+    let mut broken = Vec::new();
+
+    for id in installed_version_ids(mc_path, layout)? {
+        let problems = version_problems(mc_path, layout, &id, options, &mut java_majors);
+        if !problems.is_empty() {
+            broken.push(BrokenVersion { id, problems });
+        }
+    }
+
+    Ok(broken)
+}
+
+fn version_problems(
+    mc_path: &Path,
+    layout: &Layout,
+    id: &str,
+    options: &LaunchOptions,
+    java_majors: &mut HashMap<String, Option<u32>>,
+) -> Vec<VersionProblem> {
+    let mut problems = Vec::new();
+
+    // Walk the chain by hand so a missing parent is told apart from a broken one
+    let mut next = Some(id.to_string());
+    let mut seen = Vec::new();
+    while let Some(current) = next.take() {
+        if seen.contains(&current) {
+            problems.push(VersionProblem::InvalidJson(format!("inheritsFrom loops back to {}", current)));
+            return problems;
+        }
+
+        let installed = version_json_path(mc_path, layout, &current).exists()
+            || layout.version_dir(mc_path, &current).join(format!("{}.jar", current)).exists();
+        if !installed {
+            problems.push(VersionProblem::MissingParent(current));
+            return problems;
+        }
+
+        match read_version(mc_path, layout, &current) {
+            Ok(version_json) => next = version_json.inherits_from,
+            Err(e) => {
+                problems.push(VersionProblem::InvalidJson(e.to_string()));
+                return problems;
+            }
+        }
+        seen.push(current);
+    }
+
+    let version_json = match resolve_version(mc_path, layout, id) {
+        Ok(version_json) => version_json,
+        Err(e) => {
+            problems.push(VersionProblem::InvalidJson(e.to_string()));
+            return problems;
+        }
+    };
+
+    let client_jar = client_jar_path(mc_path, layout, &version_json);
+    if !client_jar.exists() {
+        problems.push(VersionProblem::MissingClientJar(client_jar));
+    }
+
+    // auto_java fetches the matching runtime at launch
+    let required = version_json.java_version.as_ref().and_then(|j| j.major_version);
+    if let (false, Some(required)) = (options.auto_java, required) {
+        let java = options.java_path(Some(required));
+        let found = *java_majors.entry(java.clone()).or_insert_with(|| {
+            java::detect_java(&java).ok().and_then(|info| java::major_version(&info.version))
+        });
+
+        if found.is_none_or(|found| found < required) {
+            problems.push(VersionProblem::NoCompatibleJava { java, required, found });
+        }
+    }
+
+    problems
+}
+
+fn installed_version_ids(mc_path: &Path, layout: &Layout) -> io::Result<Vec<String>> {
+    let versions_dir = mc_path.join(&layout.versions);
+    if !versions_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut ids = Vec::new();
+    for entry in fs::read_dir(&versions_dir)? {
+        let id = entry?.file_name().to_string_lossy().into_owned();
+        if version_json_path(mc_path, layout, &id).exists() {
+            ids.push(id);
+        }
+    }
+    ids.sort();
+
+    Ok(ids)
+}